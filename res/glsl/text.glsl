@@ -5,8 +5,9 @@ in vec2 uv;
 out vec4 FragColor;
 
 uniform sampler2D tex1;
+uniform float alpha;
 
 void main()
 {
-    FragColor = texture(tex1, uv).xxxx;
+    FragColor = texture(tex1, uv).xxxx * vec4(1.0, 1.0, 1.0, alpha);
 } 
\ No newline at end of file
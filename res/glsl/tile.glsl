@@ -6,12 +6,13 @@ in float out_border;
 out vec4 FragColor;
 
 uniform sampler2D tex1;
+uniform float alpha;
 
 void main()
 {
     if (uv.x < out_border || uv.x > 1 - out_border || uv.y < out_border || uv.y > 1 - out_border) {
-        FragColor = vec4(1.0);
+        FragColor = vec4(1.0, 1.0, 1.0, alpha);
     } else {
-        FragColor = texture(tex1, uv);
+        FragColor = texture(tex1, uv) * vec4(1.0, 1.0, 1.0, alpha);
     }
 } 
\ No newline at end of file
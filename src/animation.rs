@@ -0,0 +1,367 @@
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::AnimationDecoder;
+use std::io::Cursor;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use crate::focus::FocusId;
+use crate::{app_gl, App};
+
+// Longer animations are cut short rather than holding every frame in memory
+static MAX_FRAMES: usize = 240;
+// Browsers treat shorter delays as unset and play them at this speed
+static MIN_FRAME_DURATION: f32 = 0.02;
+// Frames uploaded per update once a tile takes focus, so starting an animation doesn't stall a frame
+static FRAME_UPLOADS_PER_UPDATE: usize = 4;
+// Most texture memory a playing animation may take; bigger animations stay on their first frame
+static TEXTURE_BUDGET_BYTES: usize = 96 * 1024 * 1024;
+
+#[derive(Debug)]
+struct Frame {
+    pixels: Vec<u8>,
+    duration: f32,
+}
+
+// Animated artwork kept in its encoded form; frames are decoded each time its tile takes focus
+#[derive(Debug)]
+pub struct Animation {
+    bytes: Arc<[u8]>,
+    width: u32,
+    height: u32,
+    frame_count: usize,
+}
+
+// First frame of decoded artwork, with the animation when there is more than one frame that fits the budget
+#[derive(Debug)]
+pub struct Decoded {
+    pub width: u32,
+    pub height: u32,
+    pub first_frame: Vec<u8>,
+    pub animation: Option<Animation>,
+}
+
+// Whether the bytes are in a format that can animate, judged from the header alone. PNGs only count when an
+// animation control chunk comes before the image data.
+fn may_animate(bytes: &[u8]) -> bool {
+    if bytes.starts_with(b"GIF8") {
+        return true;
+    }
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return true;
+    }
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return false;
+    }
+
+    let mut offset = 8;
+    while offset + 8 <= bytes.len() {
+        let length = u32::from_be_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]) as usize;
+        match &bytes[offset + 4..offset + 8] {
+            b"acTL" => return true,
+            b"IDAT" => return false,
+            _ => {}
+        }
+        // Length, type, data and CRC
+        offset = match offset.checked_add(length).and_then(|end| end.checked_add(12)) {
+            Some(next) => next,
+            None => return false,
+        };
+    }
+    false
+}
+
+fn frames_of(bytes: Arc<[u8]>) -> Option<Result<image::Frames<'static>, String>> {
+    let frames = if bytes.starts_with(b"GIF8") {
+        GifDecoder::new(Cursor::new(bytes)).map(|decoder| decoder.into_frames())
+    } else if bytes.starts_with(b"\x89PNG") {
+        let decoder = PngDecoder::new(Cursor::new(bytes)).ok()?;
+        if !decoder.is_apng() {
+            return None;
+        }
+        Ok(decoder.apng().into_frames())
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        WebPDecoder::new(Cursor::new(bytes)).map(|decoder| decoder.into_frames())
+    } else {
+        return None;
+    };
+    Some(frames.map_err(|err| format!("{:?}", err)))
+}
+
+fn read_frame(frame: image::ImageResult<image::Frame>) -> Result<(u32, u32, Frame), String> {
+    let frame = frame.map_err(|err| format!("Bad animation frame: {:?}", err))?;
+    let (numerator, denominator) = frame.delay().numer_denom_ms();
+    let duration = (numerator as f32 / denominator.max(1) as f32 / 1000.).max(MIN_FRAME_DURATION);
+    let buffer = frame.into_buffer();
+    let (width, height) = buffer.dimensions();
+    Ok((
+        width,
+        height,
+        Frame {
+            pixels: buffer.into_raw(),
+            duration,
+        },
+    ))
+}
+
+// Reads GIF, APNG and WebP artwork, holding on to no more than its first frame. Returns None for other
+// formats, including plain PNGs.
+pub fn decode(bytes: &[u8]) -> Option<Decoded> {
+    // Checked before copying, so still artwork isn't copied for nothing
+    if !may_animate(bytes) {
+        return None;
+    }
+    let bytes: Arc<[u8]> = Arc::from(bytes);
+    let result = frames_of(Arc::clone(&bytes))?.and_then(|frames| {
+        let mut first = None;
+        let mut frame_count = 0;
+        for frame in frames.take(MAX_FRAMES) {
+            let frame = read_frame(frame)?;
+            first.get_or_insert(frame);
+            frame_count += 1;
+        }
+        first.map(|first| (first, frame_count)).ok_or("Animation has no frames".to_string())
+    });
+
+    let ((width, height, first), frame_count) = match result {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            println!("Failed to decode animated artwork: {}", err);
+            return None;
+        }
+    };
+    let animation = Animation {
+        bytes,
+        width,
+        height,
+        frame_count,
+    };
+    Some(Decoded {
+        width,
+        height,
+        first_frame: first.pixels,
+        animation: Some(animation).filter(|e| e.frame_count > 1 && e.texture_bytes() <= TEXTURE_BUDGET_BYTES),
+    })
+}
+
+impl Animation {
+    fn texture_bytes(&self) -> usize {
+        (self.width * self.height * 4) as usize * self.frame_count
+    }
+}
+
+// Decodes the animation again on its own thread, a few frames ahead of the uploads, until playback drops the receiver
+fn spawn_decoder(bytes: Arc<[u8]>) -> Receiver<Frame> {
+    let (tx, rx): (SyncSender<Frame>, Receiver<Frame>) = mpsc::sync_channel(FRAME_UPLOADS_PER_UPDATE);
+    thread::spawn(move || {
+        let frames = match frames_of(bytes) {
+            Some(Ok(frames)) => frames,
+            Some(Err(err)) => {
+                println!("Failed to decode animated artwork: {}", err);
+                return;
+            }
+            None => return,
+        };
+        for frame in frames.take(MAX_FRAMES) {
+            let frame = match read_frame(frame) {
+                Ok((_, _, frame)) => frame,
+                Err(err) => {
+                    println!("Failed to decode animated artwork: {}", err);
+                    return;
+                }
+            };
+            if tx.send(frame).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+#[derive(Debug)]
+struct Playing {
+    c_idx: usize,
+    t_idx: usize,
+    // Frames still to come from the decoder, None once they have all been uploaded
+    decoder: Option<Receiver<Frame>>,
+    textures: Vec<(u32, f32)>,
+    frame_idx: usize,
+    frame_time: f32,
+}
+
+// Plays the focused tile's animation; every other tile shows its first frame
+#[derive(Debug, Default)]
+pub struct AnimationPlayer {
+    playing: Option<Playing>,
+}
+
+impl Drop for AnimationPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl AnimationPlayer {
+    fn stop(&mut self) {
+        if let Some(playing) = self.playing.take() {
+            for (texture_id, _) in playing.textures {
+                app_gl::release_texture(texture_id);
+            }
+        }
+    }
+
+    // Frame to draw for the tile, once all of its frames are on the GPU
+    pub fn texture_for(&self, c_idx: usize, t_idx: usize) -> Option<u32> {
+        match &self.playing {
+            Some(playing) if playing.c_idx == c_idx && playing.t_idx == t_idx => {
+                playing.textures.get(playing.frame_idx).map(|(texture_id, _)| *texture_id)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn focused_animation(app: &App) -> Option<(usize, usize, &Animation)> {
+    match app.focus.current() {
+        Some(FocusId::Tile(c_idx, t_idx)) => {
+            let animation = app.containers.get(c_idx)?.images.get(t_idx)?.animation.as_ref()?;
+            Some((c_idx, t_idx, animation))
+        }
+        _ => None,
+    }
+}
+
+// Follows focus, uploading the focused tile's frames a few at a time as they are decoded and then advancing through them
+pub fn tick(app: &mut App, dt: f32) {
+    let focused = focused_animation(app).map(|(c_idx, t_idx, animation)| (c_idx, t_idx, Arc::clone(&animation.bytes)));
+    let is_playing_focused = match (&app.animations.playing, &focused) {
+        (Some(playing), Some((c_idx, t_idx, _))) => playing.c_idx == *c_idx && playing.t_idx == *t_idx,
+        _ => false,
+    };
+    if !is_playing_focused {
+        app.animations.stop();
+        match focused {
+            Some((c_idx, t_idx, bytes)) => {
+                app.animations.playing = Some(Playing {
+                    c_idx,
+                    t_idx,
+                    decoder: Some(spawn_decoder(bytes)),
+                    textures: Vec::new(),
+                    frame_idx: 0,
+                    frame_time: 0.,
+                });
+            }
+            None => return,
+        }
+    }
+
+    let playing = match app.animations.playing.as_mut() {
+        Some(playing) => playing,
+        None => return,
+    };
+
+    if let Some(decoder) = &playing.decoder {
+        for _ in 0..FRAME_UPLOADS_PER_UPDATE {
+            match decoder.try_recv() {
+                Ok(frame) => {
+                    let (width, height) = match &app.containers[playing.c_idx].images[playing.t_idx].animation {
+                        Some(animation) => (animation.width, animation.height),
+                        None => return,
+                    };
+                    let texture_id = app_gl::upload_rgba(width, height, &frame.pixels);
+                    playing.textures.push((texture_id, frame.duration));
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    playing.decoder = None;
+                    break;
+                }
+            }
+        }
+        if playing.decoder.is_some() {
+            return;
+        }
+    }
+
+    if playing.textures.len() < 2 {
+        return;
+    }
+    playing.frame_time += dt;
+    while playing.frame_time >= playing.textures[playing.frame_idx].1 {
+        playing.frame_time -= playing.textures[playing.frame_idx].1;
+        playing.frame_idx = (playing.frame_idx + 1) % playing.textures.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, ImageEncoder, RgbaImage};
+
+    fn gif(frame_count: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            let frames = (0..frame_count).map(|i| {
+                let image = RgbaImage::from_pixel(4, 2, image::Rgba([i as u8 * 40, 0, 0, 255]));
+                image::Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(100, 1))
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn keeps_animations_encoded() {
+        let bytes = gif(3);
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!((decoded.width, decoded.height, decoded.first_frame.len()), (4, 2, 4 * 2 * 4));
+        let animation = decoded.animation.unwrap();
+        assert_eq!(animation.frame_count, 3);
+        assert_eq!(&animation.bytes[..], &bytes[..]);
+    }
+
+    #[test]
+    fn single_frames_are_not_animated() {
+        assert!(decode(&gif(1)).unwrap().animation.is_none());
+        assert!(decode(b"not an image").is_none());
+    }
+
+    #[test]
+    fn decoder_streams_every_frame() {
+        let frames: Vec<Frame> = spawn_decoder(Arc::from(&gif(3)[..])).iter().collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|frame| frame.pixels.len() == 4 * 2 * 4 && frame.duration == 0.1));
+    }
+
+    // PNG chunk with zeroed data and CRC, which the header check doesn't look at
+    fn chunk(kind: &[u8], length: usize) -> Vec<u8> {
+        let mut bytes = (length as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.resize(bytes.len() + length + 4, 0);
+        bytes
+    }
+
+    #[test]
+    fn only_animatable_formats_are_decoded() {
+        let mut still_png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut still_png)
+            .write_image(&[0; 4 * 2 * 4], 4, 2, image::ColorType::Rgba8)
+            .unwrap();
+        assert!(!may_animate(&still_png));
+        assert!(decode(&still_png).is_none());
+
+        let png_start = b"\x89PNG\r\n\x1a\n".to_vec();
+        let apng = [png_start.clone(), chunk(b"IHDR", 13), chunk(b"acTL", 8), chunk(b"IDAT", 4)].concat();
+        assert!(may_animate(&apng));
+        let late_control = [png_start, chunk(b"IHDR", 13), chunk(b"IDAT", 4), chunk(b"acTL", 8)].concat();
+        assert!(!may_animate(&late_control));
+
+        assert!(may_animate(&gif(1)));
+        assert!(may_animate(b"RIFF\0\0\0\0WEBPVP8X"));
+        assert!(!may_animate(b"\xff\xd8\xff\xe0 JPEG"));
+    }
+}
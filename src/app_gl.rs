@@ -18,8 +18,13 @@ pub struct AppGL {
     pub tile_program_id: u32,
     pub tile_program_mvp_loc: i32,
     pub tile_program_border_loc: i32,
+    pub tile_program_alpha_loc: i32,
     pub text_program_id: u32,
     pub text_program_mvp_loc: i32,
+    pub text_program_alpha_loc: i32,
+    pub color_program_id: u32,
+    pub color_program_mvp_loc: i32,
+    pub color_program_color_loc: i32,
 }
 
 #[derive(Debug)]
@@ -54,7 +59,7 @@ impl Default for TextTextureData {
     }
 }
 
-pub fn load_image_from_disk(path: &str, width: i32, height: i32) -> Result<u32, String> {
+pub fn load_image_from_disk(path: &str) -> Result<u32, String> {
     let mut f = File::open(path).unwrap();
     let mut img_bytes = Vec::new();
     f.read_to_end(&mut img_bytes).unwrap();
@@ -72,13 +77,14 @@ pub fn load_image_from_disk(path: &str, width: i32, height: i32) -> Result<u32,
             match img_data {
                 Some(img_data) => {
                     let img_data_ptr = img_data.pixel_data().as_ptr() as *const c_void;
+                    let size = img_data.size();
                     // RGBA since pixel_data pads to 4 channels
                     TexImage2D(
                         TEXTURE_2D,
                         0,
                         RGBA.try_into().unwrap(),
-                        width,
-                        height,
+                        size.x as i32,
+                        size.y as i32,
                         0,
                         RGBA,
                         UNSIGNED_BYTE,
@@ -99,52 +105,62 @@ pub fn load_image_from_disk(path: &str, width: i32, height: i32) -> Result<u32,
     }
 }
 
-pub fn load_image_from_url(client: &reqwest::blocking::Client, url: &str) -> Result<u32, String> {
-    match client.get(url).send() {
-        Ok(response) => {
-            let resp_bytes = response.bytes().unwrap();
-            unsafe {
-                let mut id: u32 = 0;
-                GenTextures(1, &mut id);
-                if id != 0 {
-                    BindTexture(TEXTURE_2D, id);
-                    TexParameteri(TEXTURE_2D, TEXTURE_WRAP_S, CLAMP_TO_EDGE.try_into().unwrap());
-                    TexParameteri(TEXTURE_2D, TEXTURE_WRAP_T, CLAMP_TO_EDGE.try_into().unwrap());
-                    TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR_MIPMAP_LINEAR.try_into().unwrap());
-                    TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR.try_into().unwrap());
-                    let img_data = Image::from_memory(&resp_bytes);
-                    match img_data {
-                        Some(img_data) => {
-                            let img_data_ptr = img_data.pixel_data().as_ptr() as *const c_void;
-                            // RGBA since pixel_data pads to 4 channels
-                            TexImage2D(
-                                TEXTURE_2D,
-                                0,
-                                RGBA.try_into().unwrap(),
-                                500,
-                                281,
-                                0,
-                                RGBA,
-                                UNSIGNED_BYTE,
-                                img_data_ptr,
-                            );
-                            GenerateMipmap(TEXTURE_2D);
-                            BindTexture(TEXTURE_2D, 0);
-                        }
-                        None => {
-                            DeleteTextures(1, &id);
-                            println!("Bad Image for url: {:?}", url);
-                            return Err("Bad Image".to_string());
-                        }
-                    }
-                }
-
-                return Ok(id);
-            }
+// Uploads decoded artwork with mipmaps so it stays smooth when drawn smaller than its size
+pub fn upload_artwork(width: u32, height: u32, pixels: &[u8]) -> u32 {
+    unsafe {
+        let mut id: u32 = 0;
+        GenTextures(1, &mut id);
+        if id != 0 {
+            BindTexture(TEXTURE_2D, id);
+            TexParameteri(TEXTURE_2D, TEXTURE_WRAP_S, CLAMP_TO_EDGE.try_into().unwrap());
+            TexParameteri(TEXTURE_2D, TEXTURE_WRAP_T, CLAMP_TO_EDGE.try_into().unwrap());
+            TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR_MIPMAP_LINEAR.try_into().unwrap());
+            TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR.try_into().unwrap());
+            TexImage2D(
+                TEXTURE_2D,
+                0,
+                RGBA.try_into().unwrap(),
+                width as i32,
+                height as i32,
+                0,
+                RGBA,
+                UNSIGNED_BYTE,
+                pixels.as_ptr() as *const c_void,
+            );
+            GenerateMipmap(TEXTURE_2D);
+            BindTexture(TEXTURE_2D, 0);
         }
-        Err(_) => {
-            return Err("Bad Image Url".to_string());
+
+        id
+    }
+}
+
+// Uploads a few raw RGBA pixels, e.g. a blurred preview, with plain linear filtering to smooth them out
+pub fn upload_rgba(width: u32, height: u32, pixels: &[u8]) -> u32 {
+    unsafe {
+        let mut id: u32 = 0;
+        GenTextures(1, &mut id);
+        if id != 0 {
+            BindTexture(TEXTURE_2D, id);
+            TexParameteri(TEXTURE_2D, TEXTURE_WRAP_S, CLAMP_TO_EDGE.try_into().unwrap());
+            TexParameteri(TEXTURE_2D, TEXTURE_WRAP_T, CLAMP_TO_EDGE.try_into().unwrap());
+            TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR.try_into().unwrap());
+            TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR.try_into().unwrap());
+            TexImage2D(
+                TEXTURE_2D,
+                0,
+                RGBA.try_into().unwrap(),
+                width as i32,
+                height as i32,
+                0,
+                RGBA,
+                UNSIGNED_BYTE,
+                pixels.as_ptr() as *const c_void,
+            );
+            BindTexture(TEXTURE_2D, 0);
         }
+
+        id
     }
 }
 
@@ -169,11 +185,21 @@ fn sw_blit_to_buffer(offset: (i32, i32), size: (u32, u32), top: i32, dst: &mut T
     }
 }
 
-fn sw_render_text_to_buffer(str: &str, data: &mut TextTextureData) {
-    static FONT_FILE: &str = "GlacialIndifference-Bold.otf";
-    let lib = freetype::Library::init().unwrap();
-    let face = lib.new_face(FONT_FILE, 0).unwrap();
-    face.set_char_size(80 * 32, 0, 100, 0).map_err(|err| println!("{:?}", err)).ok();
+thread_local! {
+    // Opened once rather than for every piece of text
+    static FONT_FACE: freetype::Face = {
+        static FONT_FILE: &str = "GlacialIndifference-Bold.otf";
+        let lib = freetype::Library::init().unwrap();
+        lib.new_face(FONT_FILE, 0).unwrap()
+    };
+}
+
+fn sw_render_text_to_buffer(str: &str, char_size: isize, data: &mut TextTextureData) {
+    FONT_FACE.with(|face| sw_render_face_to_buffer(face, str, char_size, data));
+}
+
+fn sw_render_face_to_buffer(face: &freetype::Face, str: &str, char_size: isize, data: &mut TextTextureData) {
+    face.set_char_size(char_size, 0, 100, 0).map_err(|err| println!("{:?}", err)).ok();
     let mut offset = (0i32, 0i32);
     for c in str.chars() {
         face.load_char(c as usize, freetype::face::LoadFlag::RENDER)
@@ -218,7 +244,31 @@ fn sw_render_text_to_buffer(str: &str, data: &mut TextTextureData) {
     assert!(data.data.len() == data.width * data.height, "data should be width * height");
 }
 
+// Replaces the pixels of a texture made by upload_rgba, e.g. with the next frame of a video
+pub fn update_rgba(texture_id: u32, width: u32, height: u32, pixels: &[u8]) {
+    unsafe {
+        BindTexture(TEXTURE_2D, texture_id);
+        TexSubImage2D(
+            TEXTURE_2D,
+            0,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            RGBA,
+            UNSIGNED_BYTE,
+            pixels.as_ptr() as *const c_void,
+        );
+        BindTexture(TEXTURE_2D, 0);
+    }
+}
+
 pub fn render_text_to_texture(str: &str) -> RenderedImage {
+    render_sized_text_to_texture(str, 80 * 32)
+}
+
+// Character size is in 1/64th points, as expected by freetype
+pub fn render_sized_text_to_texture(str: &str, char_size: isize) -> RenderedImage {
     unsafe {
         let mut id: u32 = 0;
         GenTextures(1, &mut id);
@@ -230,7 +280,7 @@ pub fn render_text_to_texture(str: &str) -> RenderedImage {
         PixelStorei(UNPACK_ALIGNMENT, 1);
 
         let mut texture_data = TextTextureData::default();
-        sw_render_text_to_buffer(str, &mut texture_data);
+        sw_render_text_to_buffer(str, char_size, &mut texture_data);
         let texture_data_ptr = texture_data.data.as_ptr() as *const c_void;
         TexImage2D(
             TEXTURE_2D,
@@ -415,15 +465,22 @@ impl Default for AppGL {
             let ebo = gen_buffer();
             let tile_program_id = create_and_link_program("res/glsl/tilev.glsl", "res/glsl/tile.glsl");
             let text_program_id = create_and_link_program("res/glsl/textv.glsl", "res/glsl/text.glsl");
+            let color_program_id = create_and_link_program("res/glsl/textv.glsl", "res/glsl/color.glsl");
 
             upload_buffer_data(vao, vbo, ebo);
 
             let mvp_name = "mvp\0".as_bytes();
             let border_name = "border\0".as_bytes();
+            let alpha_name = "alpha\0".as_bytes();
+            let color_name = "color\0".as_bytes();
 
             let tile_program_mvp_loc = GetUniformLocation(tile_program_id, mvp_name.as_ptr() as *const i8);
             let tile_program_border_loc = GetUniformLocation(tile_program_id, border_name.as_ptr() as *const i8);
+            let tile_program_alpha_loc = GetUniformLocation(tile_program_id, alpha_name.as_ptr() as *const i8);
             let text_program_mvp_loc = GetUniformLocation(text_program_id, mvp_name.as_ptr() as *const i8);
+            let text_program_alpha_loc = GetUniformLocation(text_program_id, alpha_name.as_ptr() as *const i8);
+            let color_program_mvp_loc = GetUniformLocation(color_program_id, mvp_name.as_ptr() as *const i8);
+            let color_program_color_loc = GetUniformLocation(color_program_id, color_name.as_ptr() as *const i8);
 
             AppGL {
                 vao,
@@ -432,8 +489,13 @@ impl Default for AppGL {
                 tile_program_id,
                 tile_program_mvp_loc,
                 tile_program_border_loc,
+                tile_program_alpha_loc,
                 text_program_id,
                 text_program_mvp_loc,
+                text_program_alpha_loc,
+                color_program_id,
+                color_program_mvp_loc,
+                color_program_color_loc,
             }
         }
     }
@@ -444,7 +506,6 @@ pub fn render(app: &crate::App, windows_size: &(u32, u32)) {
     let id = glm::identity::<f32, 4>();
 
     let ortho = glm::ortho(0.0f32, windows_size.0 as f32, 0., windows_size.1 as f32, -10., 100.);
-    let base_move = glm::make_vec3(&[windows_size.0 as f32 / 2. - 550., windows_size.1 as f32 / 2. + 350., 0.0]);
 
     unsafe {
         Clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
@@ -465,64 +526,165 @@ pub fn render(app: &crate::App, windows_size: &(u32, u32)) {
             UseProgram(app.gl.tile_program_id);
             UniformMatrix4fv(app.gl.tile_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
             Uniform1f(app.gl.tile_program_border_loc, 0.);
+            Uniform1f(app.gl.tile_program_alpha_loc, 1.);
             BindTexture(TEXTURE_2D, app.background_image_texture_id);
             DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
         }
+    }
 
-        if !app.has_tiles_loaded {
-            return;
-        }
+    crate::screen::render(app, windows_size);
+}
+
+pub fn render_home(app: &crate::App, windows_size: &(u32, u32)) {
+    let id = glm::identity::<f32, 4>();
+
+    let ortho = glm::ortho(0.0f32, windows_size.0 as f32, 0., windows_size.1 as f32, -10., 100.);
 
-        let mut render_cursor = (0., 0.);
-        for container in &app.containers {
+    unsafe {
+        for (c_idx, container) in app.containers.iter().enumerate() {
             {
-                let scale = glm::make_vec3(&[container.title.width as f32, container.title.height as f32, 1.]);
+                let rect = crate::layout::title_rect(app, c_idx, windows_size);
+                let scale = glm::make_vec3(&[rect.width, rect.height, 1.]);
                 let model = glm::scale(&id, &scale);
-                let mve = base_move
-                    + glm::make_vec3(&[
-                        app.viewport.pos[0] + (container.title.width as f32 / 2.) - 250.,
-                        app.viewport.pos[1] - render_cursor.1 as f32,
-                        0.,
-                    ]);
+                let mve = glm::make_vec3(&[rect.x, rect.y, 0.]);
                 let view = glm::translate(&id, &mve);
                 let mvp = ortho * view * model;
 
                 UseProgram(app.gl.text_program_id);
                 UniformMatrix4fv(app.gl.text_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
+                Uniform1f(app.gl.text_program_alpha_loc, container.alpha);
                 BindTexture(TEXTURE_2D, container.title.texture_id);
                 DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
-
-                render_cursor.1 += app.title_height;
             }
 
-            render_cursor.0 -= container.selected_tile_idx * app.tile_width;
-            for image in &container.images {
-                {
-                    let scale = glm::make_vec3(&[500. * image.scale, 281. * image.scale, 1.0]);
-                    let model = glm::scale(&id, &scale);
-                    let mve = base_move
-                        + glm::make_vec3(&[
-                            app.viewport.pos[0] + render_cursor.0 as f32,
-                            app.viewport.pos[1] - render_cursor.1 as f32,
-                            0.,
-                        ]);
-                    let view = glm::translate(&id, &mve);
-                    let mvp = ortho * view * model;
-
-                    UseProgram(app.gl.tile_program_id);
-                    UniformMatrix4fv(app.gl.tile_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
-                    Uniform1f(app.gl.tile_program_border_loc, image.border);
-                    BindTexture(TEXTURE_2D, image.texture_id);
-                    DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
-                    render_cursor.0 += app.tile_width;
+            for (t_idx, image) in container.images.iter().enumerate() {
+                for rect in crate::layout::tile_rects(app, c_idx, t_idx, windows_size) {
+                    // Tiles scrolled out of view are skipped before anything, their overlay text included, is rendered
+                    if !rect.is_on_screen(windows_size) {
+                        continue;
+                    }
+                    // The skeleton stays put while the artwork slides in over it
+                    let slot = crate::layout::Rect {
+                        y: rect.y - image.offset_y,
+                        ..rect
+                    };
+                    if image.state != crate::LoadState::Loaded && image.border > 0. {
+                        crate::skeleton::draw_focus_outline(app, &slot, container.alpha, windows_size);
+                    }
+                    // Whatever stands in for the artwork shows until the artwork has faded in over it
+                    let stand_in_alpha = match image.state {
+                        crate::LoadState::Loaded => (1. - image.alpha) * container.alpha,
+                        _ => container.alpha,
+                    };
+                    if stand_in_alpha > 0. {
+                        match (image.preview_texture_id, image.state) {
+                            (Some(preview_texture_id), _) => draw_image(app, &slot, preview_texture_id, stand_in_alpha, windows_size),
+                            (None, crate::LoadState::Failed) => crate::skeleton::draw_failed(app, &slot, stand_in_alpha, windows_size),
+                            (None, _) => crate::skeleton::draw(app, &slot, stand_in_alpha, windows_size),
+                        }
+                    }
+
+                    // The focused tile plays its animation once every frame is uploaded
+                    let texture_id = match image.texture() {
+                        Some(texture_id) => app.animations.texture_for(c_idx, t_idx).unwrap_or(texture_id),
+                        None => {
+                            crate::overlay::draw(app, c_idx, t_idx, &rect, windows_size);
+                            continue;
+                        }
+                    };
+                    draw_tile(app, &rect, texture_id, image.border, image.alpha * container.alpha, windows_size);
+                    // A video preview crossfades in over the artwork
+                    if let Some((video_texture_id, fade)) = app.video.frame_for(c_idx, t_idx) {
+                        draw_tile(
+                            app,
+                            &rect,
+                            video_texture_id,
+                            image.border,
+                            fade * image.alpha * container.alpha,
+                            windows_size,
+                        );
+                    }
+
+                    crate::overlay::draw(app, c_idx, t_idx, &rect, windows_size);
                 }
             }
-            render_cursor.1 += app.row_height;
-            render_cursor.0 = 0.;
+
+            // Slots a loading row expects to fill once it learns its items
+            for t_idx in container.images.len()..container.slot_count() {
+                let rect = crate::layout::slot_rect(app, c_idx, t_idx, windows_size);
+                crate::skeleton::draw(app, &rect, container.alpha, windows_size);
+            }
         }
     }
 }
 
+fn ortho(windows_size: &(u32, u32)) -> glm::Mat4 {
+    glm::ortho(0.0f32, windows_size.0 as f32, 0., windows_size.1 as f32, -10., 100.)
+}
+
+fn rect_mvp(rect: &crate::layout::Rect, windows_size: &(u32, u32)) -> glm::Mat4 {
+    let id = glm::identity::<f32, 4>();
+    let model = glm::scale(&id, &glm::make_vec3(&[rect.width, rect.height, 1.]));
+    let view = glm::translate(&id, &glm::make_vec3(&[rect.x, rect.y, 0.]));
+    ortho(windows_size) * view * model
+}
+
+// Rect covering the whole window
+pub fn screen_rect(windows_size: &(u32, u32)) -> crate::layout::Rect {
+    crate::layout::Rect {
+        x: windows_size.0 as f32 / 2.,
+        y: windows_size.1 as f32 / 2.,
+        width: windows_size.0 as f32,
+        height: windows_size.1 as f32,
+    }
+}
+
+pub fn draw_color_rect(app: &crate::App, rect: &crate::layout::Rect, color: [f32; 4], windows_size: &(u32, u32)) {
+    let mvp = rect_mvp(rect, windows_size);
+    unsafe {
+        UseProgram(app.gl.color_program_id);
+        UniformMatrix4fv(app.gl.color_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
+        Uniform4f(app.gl.color_program_color_loc, color[0], color[1], color[2], color[3]);
+        DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
+    }
+}
+
+pub fn draw_image(app: &crate::App, rect: &crate::layout::Rect, texture_id: u32, alpha: f32, windows_size: &(u32, u32)) {
+    let mvp = rect_mvp(rect, windows_size);
+    unsafe {
+        UseProgram(app.gl.tile_program_id);
+        UniformMatrix4fv(app.gl.tile_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
+        Uniform1f(app.gl.tile_program_border_loc, 0.);
+        Uniform1f(app.gl.tile_program_alpha_loc, alpha);
+        BindTexture(TEXTURE_2D, texture_id);
+        DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
+    }
+}
+
+// Artwork drawn with the tile shader's focus border
+pub fn draw_tile(app: &crate::App, rect: &crate::layout::Rect, texture_id: u32, border: f32, alpha: f32, windows_size: &(u32, u32)) {
+    let mvp = rect_mvp(rect, windows_size);
+    unsafe {
+        UseProgram(app.gl.tile_program_id);
+        UniformMatrix4fv(app.gl.tile_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
+        Uniform1f(app.gl.tile_program_border_loc, border);
+        Uniform1f(app.gl.tile_program_alpha_loc, alpha);
+        BindTexture(TEXTURE_2D, texture_id);
+        DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
+    }
+}
+
+pub fn draw_text(app: &crate::App, rect: &crate::layout::Rect, texture_id: u32, alpha: f32, windows_size: &(u32, u32)) {
+    let mvp = rect_mvp(rect, windows_size);
+    unsafe {
+        UseProgram(app.gl.text_program_id);
+        UniformMatrix4fv(app.gl.text_program_mvp_loc, 1, FALSE, mvp.data.as_slice().as_ptr());
+        Uniform1f(app.gl.text_program_alpha_loc, alpha);
+        BindTexture(TEXTURE_2D, texture_id);
+        DrawElements(TRIANGLES, 6, UNSIGNED_INT, 0 as *const c_void);
+    }
+}
+
 impl Drop for AppGL {
     fn drop(&mut self) {
         unsafe {
@@ -531,6 +693,7 @@ impl Drop for AppGL {
             DeleteVertexArrays(1, &self.vao);
             DeleteProgram(self.tile_program_id);
             DeleteProgram(self.text_program_id);
+            DeleteProgram(self.color_program_id);
             gl_loader::end_gl();
         }
     }
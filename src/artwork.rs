@@ -0,0 +1,47 @@
+use sfml::graphics::Image;
+
+use crate::{animation, app_gl, preview};
+
+// Artwork decoded off the main thread, ready to be uploaded as a texture
+#[derive(Debug)]
+pub struct Artwork {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    // Animated artwork, played while its tile has focus
+    pub animation: Option<animation::Animation>,
+}
+
+impl Artwork {
+    pub fn upload(&self) -> u32 {
+        app_gl::upload_artwork(self.width, self.height, &self.pixels)
+    }
+}
+
+// Decodes downloaded artwork, keeping a preview of it in the disk cache when asked. Formats SFML can't
+// decode, like WebP, are shown from their first decoded frame instead.
+pub fn decode(url: &str, bytes: &[u8], cache_preview: bool) -> Result<Artwork, String> {
+    let decoded = animation::decode(bytes);
+    let (width, height, pixels) = match Image::from_memory(bytes) {
+        Some(image) => {
+            if cache_preview {
+                if let Err(err) = preview::store_in_cache(url, &image) {
+                    println!("{}", err);
+                }
+            }
+            let size = image.size();
+            (size.x, size.y, image.pixel_data().to_vec())
+        }
+        None => match &decoded {
+            Some(decoded) => (decoded.width, decoded.height, decoded.first_frame.clone()),
+            None => return Err(format!("Bad Image for url: {:?}", url)),
+        },
+    };
+
+    Ok(Artwork {
+        width,
+        height,
+        pixels,
+        animation: decoded.and_then(|decoded| decoded.animation),
+    })
+}
@@ -0,0 +1,236 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::util;
+
+// Upper bound on simulation steps per frame so a long stall can't snowball
+static MAX_STEPS_PER_FRAME: u32 = 8;
+
+// Source of elapsed real time, swappable so simulation can be driven deterministically
+pub trait TimeSource {
+    // Seconds passed since the previous call
+    fn elapsed(&mut self) -> f32;
+}
+
+impl TimeSource for util::Timer {
+    fn elapsed(&mut self) -> f32 {
+        self.dt()
+    }
+}
+
+// Time source that only moves when told to; clones share the same pending time
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    pending: Rc<Cell<f32>>,
+}
+
+impl ManualClock {
+    pub fn advance(&self, seconds: f32) {
+        self.pending.set(self.pending.get() + seconds);
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn elapsed(&mut self) -> f32 {
+        self.pending.replace(0.)
+    }
+}
+
+// Converts real time into a whole number of fixed simulation steps per frame
+pub struct Clock {
+    source: Box<dyn TimeSource>,
+    pub step: f32,
+    pub time_scale: f32,
+    pub paused: bool,
+    accumulator: f32,
+    step_requested: bool,
+    // Simulated seconds so far, for timing that has to follow pauses, slow motion and manual clocks
+    time: f64,
+    // Real seconds covered by the last advance, whether or not the simulation ran
+    frame_time: f32,
+}
+
+impl Clock {
+    pub fn new(source: Box<dyn TimeSource>, step: f32) -> Self {
+        Clock {
+            source,
+            step,
+            time_scale: 1.,
+            paused: false,
+            accumulator: 0.,
+            step_requested: false,
+            time: 0.,
+            frame_time: 0.,
+        }
+    }
+
+    // Number of fixed steps the simulation should run this frame
+    pub fn advance(&mut self) -> u32 {
+        let elapsed = self.source.elapsed();
+        self.frame_time = elapsed;
+        // Time spent paused is dropped, and the frame stays where it was
+        if self.paused {
+            if self.step_requested {
+                self.step_requested = false;
+                // Show the stepped state outright rather than the one before it
+                self.accumulator = self.step;
+                self.time += self.step as f64;
+                return 1;
+            }
+            return 0;
+        }
+
+        self.accumulator += elapsed * self.time_scale;
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < MAX_STEPS_PER_FRAME {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if steps == MAX_STEPS_PER_FRAME {
+            self.accumulator = self.accumulator.min(self.step);
+        }
+
+        self.time += steps as f64 * self.step as f64;
+        steps
+    }
+
+    // How far between the last two simulation steps the current frame falls
+    pub fn alpha(&self) -> f32 {
+        util::clamp(self.accumulator / self.step, 0., 1.)
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // For things that follow the user rather than the simulation, like held keys, which should neither
+    // stop while paused nor slow down in slow motion
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn toggle_slow_motion(&mut self, slow_scale: f32) {
+        self.time_scale = if self.time_scale == 1. { slow_scale } else { 1. };
+    }
+
+    // Runs exactly one step on the next advance while paused
+    pub fn request_step(&mut self) {
+        self.step_requested = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static STEP: f32 = 0.25;
+
+    fn manual_clock() -> (ManualClock, Clock) {
+        let time = ManualClock::default();
+        let clock = Clock::new(Box::new(time.clone()), STEP);
+        (time, clock)
+    }
+
+    #[test]
+    fn runs_whole_steps_and_carries_the_rest() {
+        let (time, mut clock) = manual_clock();
+        time.advance(0.625);
+        assert_eq!(clock.advance(), 2);
+        assert_eq!(clock.alpha(), 0.5);
+        time.advance(0.125);
+        assert_eq!(clock.advance(), 1);
+        assert_eq!(clock.alpha(), 0.);
+        assert_eq!(clock.advance(), 0);
+    }
+
+    #[test]
+    fn long_stalls_are_clamped() {
+        let (time, mut clock) = manual_clock();
+        time.advance(STEP * 100.);
+        assert_eq!(clock.advance(), MAX_STEPS_PER_FRAME);
+        // The backlog is dropped rather than paid back over later frames
+        assert!(clock.advance() <= 1);
+    }
+
+    #[test]
+    fn pausing_stops_steps_until_one_is_requested() {
+        let (time, mut clock) = manual_clock();
+        clock.toggle_pause();
+        time.advance(1.);
+        assert_eq!(clock.advance(), 0);
+
+        clock.request_step();
+        time.advance(1.);
+        assert_eq!(clock.advance(), 1);
+        assert_eq!(clock.alpha(), 1.);
+        assert_eq!(clock.advance(), 0);
+        assert_eq!(clock.alpha(), 1.);
+        assert_eq!(clock.time(), STEP as f64);
+    }
+
+    #[test]
+    fn paused_time_is_not_caught_up() {
+        let (time, mut clock) = manual_clock();
+        clock.toggle_pause();
+        time.advance(1.);
+        clock.advance();
+        clock.toggle_pause();
+        assert_eq!(clock.advance(), 0);
+    }
+
+    #[test]
+    fn time_scale_slows_the_simulation() {
+        let (time, mut clock) = manual_clock();
+        clock.toggle_slow_motion(0.25);
+        time.advance(1.);
+        assert_eq!(clock.advance(), 1);
+        clock.toggle_slow_motion(0.25);
+        time.advance(1.);
+        assert_eq!(clock.advance(), 4);
+    }
+
+    #[test]
+    fn frame_time_ignores_pause_and_slow_motion() {
+        let (time, mut clock) = manual_clock();
+        clock.toggle_slow_motion(0.25);
+        time.advance(0.5);
+        clock.advance();
+        assert_eq!(clock.frame_time(), 0.5);
+
+        clock.toggle_pause();
+        time.advance(0.5);
+        assert_eq!(clock.advance(), 0);
+        assert_eq!(clock.frame_time(), 0.5);
+    }
+
+    // Runs a tween to completion on the clock, with the frames falling as `frames` says
+    fn tween_values(frames: &[f32]) -> Vec<f32> {
+        use crate::tween::{Property, Tween, Tweens};
+
+        let (time, mut clock) = manual_clock();
+        let mut tweens = Tweens::default();
+        tweens.start(Tween::new(Property::ViewportY, 0., 100.).with_speed(1.));
+        let mut values = Vec::new();
+        for frame in frames {
+            time.advance(*frame);
+            for _ in 0..clock.advance() {
+                let (stepped, _) = tweens.advance(clock.step);
+                values.extend(stepped.iter().map(|(_, value)| *value));
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn tweens_step_the_same_however_frames_fall() {
+        let even = tween_values(&[0.25, 0.25, 0.25, 0.25]);
+        let uneven = tween_values(&[0.1, 0.5, 0.05, 0.35]);
+        assert_eq!(even, vec![25., 50., 75., 100.]);
+        assert_eq!(uneven, even);
+    }
+}
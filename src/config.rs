@@ -0,0 +1,380 @@
+use std::fs::File;
+use std::io::Read;
+
+static DEFAULT_CONFIG_PATH: &str = "config.toml";
+static ENV_PREFIX: &str = "SFML_EXAMPLE_";
+// Retries back off exponentially, so past this a request would wait hours between attempts
+static MAX_RETRIES: u32 = 10;
+
+// Where a setting's value came from, shown by --print-config
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    File(String),
+    Env(String),
+    Cli,
+}
+
+impl Source {
+    fn describe(&self) -> String {
+        match self {
+            Source::Default => "default".to_string(),
+            Source::File(path) => format!("file {}", path),
+            Source::Env(name) => format!("env {}", name),
+            Source::Cli => "command line".to_string(),
+        }
+    }
+}
+
+// Window, loading and layout options, settled once at startup
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub window_width: u32,
+    pub window_height: u32,
+    // Frame rate limit; 0 leaves it unlimited
+    pub fps: u32,
+    // Threads running the loading runtime
+    pub workers: usize,
+    // Requests allowed in flight at once, shared across all hosts
+    pub max_requests: usize,
+    pub timeout_secs: u64,
+    // Further attempts made after a request times out or the server fails
+    pub retries: u32,
+    pub page_url: String,
+    // Set data url, with {{id}} standing for the set id
+    pub set_url: String,
+    pub row_height: f32,
+    pub title_height: f32,
+    // Landscape tile size; the other row styles are scaled to match
+    pub tile_width: f32,
+    pub tile_height: f32,
+    pub print_config: bool,
+    sources: Vec<(&'static str, Source)>,
+}
+
+// Every setting as `section.name`, the form used by the file, env vars and the command line
+static KEYS: [&str; 13] = [
+    "window.width",
+    "window.height",
+    "window.fps",
+    "loading.workers",
+    "loading.max_requests",
+    "loading.timeout_secs",
+    "loading.retries",
+    "loading.page_url",
+    "loading.set_url",
+    "layout.row_height",
+    "layout.title_height",
+    "layout.tile_width",
+    "layout.tile_height",
+];
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            window_width: 1920,
+            window_height: 1080,
+            fps: 200,
+            workers: num_cpus::get().saturating_sub(1).max(1),
+            max_requests: 12,
+            timeout_secs: 10,
+            retries: 3,
+            page_url: "https://cd-static.bamgrid.com/dp-117731241344/home.json".to_string(),
+            set_url: "https://cd-static.bamgrid.com/dp-117731241344/sets/{{id}}.json".to_string(),
+            row_height: 280.,
+            title_height: 200.,
+            tile_width: 500.,
+            tile_height: 281.,
+            print_config: false,
+            sources: KEYS.iter().map(|key| (*key, Source::Default)).collect(),
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("Invalid value for {}: {:?}", key, value))
+}
+
+fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+impl Config {
+    // Layers defaults, then the config file, then env vars, then command line args, and checks the result
+    pub fn load(args: &[String]) -> Result<Config, String> {
+        let cli = parse_args(args)?;
+        let mut config = Config::default();
+
+        // The file itself can be picked by env var or command line
+        let path = match cli.iter().find(|(key, _)| key == "config") {
+            Some((_, path)) => Some(path.to_string()),
+            None => std::env::var(format!("{}CONFIG", ENV_PREFIX)).ok(),
+        };
+        match &path {
+            Some(path) => config.apply_file(path)?,
+            None => {
+                // The default file is optional
+                if std::path::Path::new(DEFAULT_CONFIG_PATH).exists() {
+                    config.apply_file(DEFAULT_CONFIG_PATH)?;
+                }
+            }
+        }
+
+        for key in KEYS.iter() {
+            let name = env_name(key);
+            if let Ok(value) = std::env::var(&name) {
+                config.set(key, &value, Source::Env(name))?;
+            }
+        }
+
+        for (key, value) in &cli {
+            match key.as_str() {
+                "config" => {}
+                "print-config" => config.print_config = true,
+                _ => config.set(key, value, Source::Cli)?,
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Reads a TOML file of the form [window] width = 1280 ... on top of the current values
+    fn apply_file(&mut self, path: &str) -> Result<(), String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open config file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read config file {}: {:?}", path, err))?;
+        let data: toml::Value = contents.parse().map_err(|err| format!("Malformed config file {}: {}", path, err))?;
+        let sections = data.as_table().ok_or(format!("Config file {} should be a table", path))?;
+
+        for (section, entries) in sections {
+            let entries = entries
+                .as_table()
+                .ok_or(format!("Config file {} should only hold sections, found {:?}", path, section))?;
+            for (name, value) in entries {
+                let key = format!("{}.{}", section, name);
+                let value = match value {
+                    toml::Value::String(value) => value.to_string(),
+                    toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => value.to_string(),
+                    _ => return Err(format!("Config value {} in {} should be a string or number", key, path)),
+                };
+                self.set(&key, &value, Source::File(path.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: &str, source: Source) -> Result<(), String> {
+        match key {
+            "window.width" => self.window_width = parse(key, value)?,
+            "window.height" => self.window_height = parse(key, value)?,
+            "window.fps" => self.fps = parse(key, value)?,
+            "loading.workers" => self.workers = parse(key, value)?,
+            "loading.max_requests" => self.max_requests = parse(key, value)?,
+            "loading.timeout_secs" => self.timeout_secs = parse(key, value)?,
+            "loading.retries" => self.retries = parse(key, value)?,
+            "loading.page_url" => self.page_url = value.to_string(),
+            "loading.set_url" => self.set_url = value.to_string(),
+            "layout.row_height" => self.row_height = parse(key, value)?,
+            "layout.title_height" => self.title_height = parse(key, value)?,
+            "layout.tile_width" => self.tile_width = parse(key, value)?,
+            "layout.tile_height" => self.tile_height = parse(key, value)?,
+            _ => return Err(format!("Unknown config setting: {:?}", key)),
+        }
+        if let Some(entry) = self.sources.iter_mut().find(|(k, _)| *k == key) {
+            entry.1 = source;
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.window_width == 0 || self.window_height == 0 {
+            return Err(format!(
+                "Window size must not be zero, got {}x{}",
+                self.window_width, self.window_height
+            ));
+        }
+        if self.workers == 0 {
+            return Err("At least one loading thread is needed".to_string());
+        }
+        if self.max_requests == 0 || self.timeout_secs == 0 {
+            return Err(format!(
+                "loading.max_requests and loading.timeout_secs must not be zero, got {} and {}",
+                self.max_requests, self.timeout_secs
+            ));
+        }
+        if self.retries > MAX_RETRIES {
+            return Err(format!("loading.retries must be at most {}, got {}", MAX_RETRIES, self.retries));
+        }
+        for (key, url) in [("loading.page_url", &self.page_url), ("loading.set_url", &self.set_url)] {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(format!("{} should be an http(s) url, got {:?}", key, url));
+            }
+        }
+        if !self.set_url.contains("{{id}}") {
+            return Err(format!("loading.set_url should contain {{{{id}}}}, got {:?}", self.set_url));
+        }
+        if self.row_height <= 0. || self.title_height < 0. {
+            return Err(format!(
+                "Layout heights must be positive, got row_height {} and title_height {}",
+                self.row_height, self.title_height
+            ));
+        }
+        if self.tile_width <= 0. || self.tile_height <= 0. {
+            return Err(format!("Tile size must be positive, got {}x{}", self.tile_width, self.tile_height));
+        }
+        Ok(())
+    }
+
+    pub fn window_size(&self) -> (u32, u32) {
+        (self.window_width, self.window_height)
+    }
+
+    pub fn tile_size(&self) -> (f32, f32) {
+        (self.tile_width, self.tile_height)
+    }
+
+    // Final settings as TOML, each noting where its value came from
+    pub fn describe(&self) -> String {
+        let values: Vec<(&str, String)> = vec![
+            ("window.width", self.window_width.to_string()),
+            ("window.height", self.window_height.to_string()),
+            ("window.fps", self.fps.to_string()),
+            ("loading.workers", self.workers.to_string()),
+            ("loading.max_requests", self.max_requests.to_string()),
+            ("loading.timeout_secs", self.timeout_secs.to_string()),
+            ("loading.retries", self.retries.to_string()),
+            ("loading.page_url", format!("{:?}", self.page_url)),
+            ("loading.set_url", format!("{:?}", self.set_url)),
+            ("layout.row_height", format!("{:?}", self.row_height)),
+            ("layout.title_height", format!("{:?}", self.title_height)),
+            ("layout.tile_width", format!("{:?}", self.tile_width)),
+            ("layout.tile_height", format!("{:?}", self.tile_height)),
+        ];
+
+        let mut text = String::new();
+        let mut current_section = "";
+        for (key, value) in values {
+            let (section, name) = key.split_once('.').unwrap_or(("", key));
+            if section != current_section {
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&format!("[{}]\n", section));
+                current_section = section;
+            }
+            let source = self
+                .sources
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, source)| source.describe())
+                .unwrap_or_default();
+            text.push_str(&format!("{} = {} # {}\n", name, value, source));
+        }
+        text
+    }
+}
+
+// Splits args of the form --key value, --key=value or a bare --flag
+fn parse_args(args: &[String]) -> Result<Vec<(String, String)>, String> {
+    let mut parsed = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let arg = arg
+            .strip_prefix("--")
+            .ok_or(format!("Unexpected argument {:?}, settings look like --window.width 1280", arg))?;
+        if let Some((key, value)) = arg.split_once('=') {
+            parsed.push((key.to_string(), value.to_string()));
+        } else if arg == "print-config" {
+            parsed.push((arg.to_string(), String::new()));
+        } else {
+            let value = args.next().ok_or(format!("Missing value for --{}", arg))?;
+            parsed.push((arg.to_string(), value.to_string()));
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let path = std::env::temp_dir().join(format!("sfml_example_config_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        std::fs::write(&path, "[window]\nwidth = 1280\nheight = 720\n\n[loading]\nretries = 5\n").unwrap();
+        std::env::set_var("SFML_EXAMPLE_WINDOW_HEIGHT", "800");
+        std::env::set_var("SFML_EXAMPLE_LOADING_RETRIES", "6");
+        let config = Config::load(&args(&["--config", &path, "--loading.retries", "7"]));
+        std::env::remove_var("SFML_EXAMPLE_WINDOW_HEIGHT");
+        std::env::remove_var("SFML_EXAMPLE_LOADING_RETRIES");
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!(config.fps, 200);
+        assert_eq!(config.window_width, 1280);
+        assert_eq!(config.window_height, 800);
+        assert_eq!(config.retries, 7);
+
+        let description = config.describe();
+        assert!(description.contains("fps = 200 # default\n"));
+        assert!(description.contains(&format!("width = 1280 # file {}\n", path)));
+        assert!(description.contains("height = 800 # env SFML_EXAMPLE_WINDOW_HEIGHT\n"));
+        assert!(description.contains("retries = 7 # command line\n"));
+        assert!(description.starts_with("[window]\n"));
+        assert!(description.contains("\n\n[loading]\n"));
+    }
+
+    #[test]
+    fn parses_each_argument_form() {
+        assert_eq!(
+            parse_args(&args(&["--window.width", "1280", "--window.fps=60", "--print-config"])).unwrap(),
+            vec![
+                ("window.width".to_string(), "1280".to_string()),
+                ("window.fps".to_string(), "60".to_string()),
+                ("print-config".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_args(&args(&["--window.width"])).unwrap_err().contains("Missing value"));
+        assert!(parse_args(&args(&["window.width", "1280"]))
+            .unwrap_err()
+            .contains("Unexpected argument"));
+    }
+
+    #[test]
+    fn rejects_unknown_or_malformed_settings() {
+        let mut config = Config::default();
+        assert!(config
+            .set("window.depth", "3", Source::Cli)
+            .unwrap_err()
+            .contains("Unknown config setting"));
+        assert!(config
+            .set("window.width", "wide", Source::Cli)
+            .unwrap_err()
+            .contains("Invalid value"));
+    }
+
+    #[test]
+    fn validation_catches_unusable_values() {
+        let invalid = |change: fn(&mut Config)| {
+            let mut config = Config::default();
+            change(&mut config);
+            config.validate().unwrap_err()
+        };
+        assert!(Config::default().validate().is_ok());
+        assert!(invalid(|c| c.window_width = 0).contains("Window size"));
+        assert!(invalid(|c| c.workers = 0).contains("loading thread"));
+        assert!(invalid(|c| c.max_requests = 0).contains("max_requests"));
+        assert!(invalid(|c| c.retries = MAX_RETRIES + 1).contains("retries"));
+        assert!(invalid(|c| c.page_url = "ftp://example.com".to_string()).contains("page_url"));
+        assert!(invalid(|c| c.set_url = "https://example.com/sets".to_string()).contains("{{id}}"));
+        assert!(invalid(|c| c.row_height = 0.).contains("Layout heights"));
+        assert!(invalid(|c| c.tile_height = -1.).contains("Tile size"));
+    }
+}
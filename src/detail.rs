@@ -0,0 +1,345 @@
+use sfml::window::mouse::Button;
+use sfml::window::Event;
+use std::sync::mpsc::Receiver;
+
+use crate::focus::{self, Direction, FocusId, Focusable};
+use crate::input::Action;
+use crate::layout::{self, Rect};
+use crate::row_style::RowStyle;
+use crate::screen::{Screen, ScreenKind, Transition};
+use crate::sound::SoundEvent;
+use crate::tween::{self, Property};
+use crate::{app_gl, artwork, motion, skeleton, util, App};
+
+static TITLE_CHAR_SIZE: isize = 64 * 32;
+static METADATA_CHAR_SIZE: isize = 40 * 32;
+static DESCRIPTION_CHAR_SIZE: isize = 32 * 32;
+static BUTTON_CHAR_SIZE: isize = 36 * 32;
+static DESCRIPTION_LINE_CHARS: usize = 48;
+static DESCRIPTION_MAX_LINES: usize = 8;
+
+static BUTTON_LABELS: [&str; 2] = ["Play", "Add to Watchlist"];
+static BUTTON_PLAY: usize = 0;
+static BUTTON_WATCHLIST: usize = 1;
+
+// Home scroll position to put back exactly when the detail page closes
+#[derive(Debug)]
+struct SavedScroll {
+    selected_container_idx: usize,
+    viewport_y: f32,
+    row_scroll: Vec<f32>,
+    focus: Option<FocusId>,
+}
+
+#[derive(Debug)]
+pub struct Detail {
+    // Tile the page grew out of and shrinks back into
+    pub from_rect: Rect,
+    // The tile's blurred preview, or None, while its own artwork is still loading
+    pub tile_texture_id: Option<u32>,
+    pub hero_texture_id: Option<u32>,
+    hero_rx: Receiver<artwork::Artwork>,
+    pub title: app_gl::RenderedImage,
+    pub metadata: app_gl::RenderedImage,
+    pub description: Vec<app_gl::RenderedImage>,
+    pub buttons: Vec<app_gl::RenderedImage>,
+    pub focused_button: usize,
+    pub in_watchlist: bool,
+    saved: SavedScroll,
+}
+
+impl Drop for Detail {
+    fn drop(&mut self) {
+        app_gl::release_texture(self.title.texture_id);
+        app_gl::release_texture(self.metadata.texture_id);
+        for line in &self.description {
+            app_gl::release_texture(line.texture_id);
+        }
+        for button in &self.buttons {
+            app_gl::release_texture(button.texture_id);
+        }
+        if let Some(texture_id) = self.hero_texture_id {
+            app_gl::release_texture(texture_id);
+        }
+    }
+}
+
+// Greedy word wrap by character count
+fn wrap_text(text: &str, line_chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > line_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn hero_rect(window_size: &(u32, u32)) -> Rect {
+    // Keeps the shape of the landscape artwork it's drawn from
+    let (tile_width, tile_height) = RowStyle::Landscape.tile_size();
+    let width = window_size.0 as f32 * 0.6;
+    let height = width * tile_height / tile_width;
+    Rect {
+        x: window_size.0 as f32 - width / 2.,
+        y: window_size.1 as f32 - height / 2.,
+        width,
+        height,
+    }
+}
+
+// Text is laid out left aligned from a fixed margin
+fn text_rect(image: &app_gl::RenderedImage, top: f32) -> Rect {
+    static MARGIN: f32 = 100.;
+    Rect {
+        x: MARGIN + image.width as f32 / 2.,
+        y: top - image.height as f32 / 2.,
+        width: image.width as f32,
+        height: image.height as f32,
+    }
+}
+
+pub fn title_rect(detail: &Detail, window_size: &(u32, u32)) -> Rect {
+    text_rect(&detail.title, window_size.1 as f32 - 120.)
+}
+
+pub fn metadata_rect(detail: &Detail, window_size: &(u32, u32)) -> Rect {
+    text_rect(&detail.metadata, window_size.1 as f32 - 240.)
+}
+
+pub fn description_rect(detail: &Detail, line_idx: usize, window_size: &(u32, u32)) -> Rect {
+    static LINE_HEIGHT: f32 = 50.;
+    text_rect(
+        &detail.description[line_idx],
+        window_size.1 as f32 - 330. - LINE_HEIGHT * line_idx as f32,
+    )
+}
+
+pub fn button_rect(button_idx: usize) -> Rect {
+    static BUTTON_SIZE: (f32, f32) = (360., 90.);
+    static BUTTON_GAP: f32 = 40.;
+    Rect {
+        x: 100. + BUTTON_SIZE.0 / 2. + (BUTTON_SIZE.0 + BUTTON_GAP) * button_idx as f32,
+        y: 160.,
+        width: BUTTON_SIZE.0,
+        height: BUTTON_SIZE.1,
+    }
+}
+
+// Where the hero artwork is drawn for the current transition progress
+pub fn transition_rect(detail: &Detail, progress: f32, window_size: &(u32, u32)) -> Rect {
+    let to = hero_rect(window_size);
+    let from = detail.from_rect;
+    // Ease out so the artwork settles gently into place
+    let t = 1. - (1. - progress).powi(3);
+    Rect {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+        width: from.width + (to.width - from.width) * t,
+        height: from.height + (to.height - from.height) * t,
+    }
+}
+
+fn button_focusables(detail: &Detail) -> Vec<Focusable> {
+    (0..detail.buttons.len())
+        .map(|idx| Focusable {
+            id: FocusId::Button(idx),
+            rect: button_rect(idx),
+        })
+        .collect()
+}
+
+impl Detail {
+    // Builds the detail page for a loaded tile, growing out of where the tile is drawn right now
+    pub fn open(app: &App, c_idx: usize, t_idx: usize) -> Option<Detail> {
+        Detail::open_from(app, c_idx, t_idx, layout::tile_rect(app, c_idx, t_idx, &app.window_size))
+    }
+
+    // Same as `open` for tiles drawn somewhere other than the home rows
+    pub fn open_from(app: &App, c_idx: usize, t_idx: usize, from_rect: Rect) -> Option<Detail> {
+        let tile = app.containers.get(c_idx).and_then(|c| c.images.get(t_idx))?;
+
+        let item = &tile.item;
+        let description = wrap_text(&item.description, DESCRIPTION_LINE_CHARS)
+            .iter()
+            .take(DESCRIPTION_MAX_LINES)
+            .map(|line| app_gl::render_sized_text_to_texture(line, DESCRIPTION_CHAR_SIZE))
+            .collect();
+
+        Some(Detail {
+            from_rect,
+            tile_texture_id: tile.texture().or(tile.preview_texture_id),
+            hero_texture_id: None,
+            hero_rx: app.loader.load_artwork(item.hero_url()),
+            title: app_gl::render_sized_text_to_texture(&item.title, TITLE_CHAR_SIZE),
+            metadata: app_gl::render_sized_text_to_texture(&item.metadata_line(), METADATA_CHAR_SIZE),
+            description,
+            buttons: BUTTON_LABELS
+                .iter()
+                .map(|label| app_gl::render_sized_text_to_texture(label, BUTTON_CHAR_SIZE))
+                .collect(),
+            focused_button: BUTTON_PLAY,
+            in_watchlist: false,
+            saved: SavedScroll {
+                selected_container_idx: app.selected_container_idx,
+                // Settled positions, so a page opened mid-scroll returns to where the scroll was heading
+                viewport_y: app.viewport.target_y,
+                row_scroll: app.containers.iter().map(|c| c.scroll_target).collect(),
+                focus: app.focus.current(),
+            },
+        })
+    }
+
+    fn activate_button(&mut self, app: &mut App, button_idx: usize) {
+        if button_idx == BUTTON_PLAY {
+            // Nothing plays items yet, so Play answers like any other dead end
+            app.sounds.play(SoundEvent::Bump);
+        } else if button_idx == BUTTON_WATCHLIST {
+            app.sounds.play(SoundEvent::Select);
+            self.in_watchlist = !self.in_watchlist;
+            let label = if self.in_watchlist {
+                "Remove from Watchlist"
+            } else {
+                BUTTON_LABELS[BUTTON_WATCHLIST]
+            };
+            let old = std::mem::replace(
+                &mut self.buttons[BUTTON_WATCHLIST],
+                app_gl::render_sized_text_to_texture(label, BUTTON_CHAR_SIZE),
+            );
+            app_gl::release_texture(old.texture_id);
+        }
+    }
+
+    fn focus_button_towards(&mut self, direction: Direction) {
+        let from = button_rect(self.focused_button);
+        if let Some(FocusId::Button(idx)) = focus::find_next(&from, direction, &button_focusables(self)) {
+            self.focused_button = idx;
+        }
+    }
+
+    fn button_at(&self, x: f32, y: f32) -> Option<usize> {
+        (0..self.buttons.len()).find(|idx| button_rect(*idx).contains(x, y))
+    }
+}
+
+impl Screen for Detail {
+    fn kind(&self) -> ScreenKind {
+        ScreenKind::Detail
+    }
+
+    fn handle_action(&mut self, app: &mut App, action: Action) -> Transition {
+        match action {
+            Action::Back => return Transition::Pop,
+            Action::Select => self.activate_button(app, self.focused_button),
+            Action::Up => self.focus_button_towards(Direction::Up),
+            Action::Down => self.focus_button_towards(Direction::Down),
+            Action::Left => self.focus_button_towards(Direction::Left),
+            Action::Right => self.focus_button_towards(Direction::Right),
+            _ => {}
+        }
+        Transition::None
+    }
+
+    // Hovering focuses a button and clicking activates it; the home rows underneath ignore the mouse
+    fn map_mouse_event(&mut self, _app: &mut App, event: &Event, window_size: &(u32, u32)) -> Option<Action> {
+        match *event {
+            Event::MouseMoved { x, y } => {
+                let (x, y) = layout::from_window_coords(x, y, window_size);
+                if let Some(idx) = self.button_at(x, y) {
+                    self.focused_button = idx;
+                }
+                None
+            }
+            Event::MouseButtonReleased {
+                button: Button::LEFT,
+                x,
+                y,
+            } => {
+                let (x, y) = layout::from_window_coords(x, y, window_size);
+                self.focused_button = self.button_at(x, y)?;
+                Some(Action::Select)
+            }
+            _ => None,
+        }
+    }
+
+    // Picks up the hero artwork once the background load finishes
+    fn update(&mut self, _app: &mut App, _dt: f32) {
+        if let Ok(artwork) = self.hero_rx.try_recv() {
+            self.hero_texture_id = Some(artwork.upload());
+        }
+    }
+
+    // Puts the home page back exactly as it was when the page opened
+    fn on_exit(&mut self, app: &mut App) {
+        let saved = &self.saved;
+        app.selected_container_idx = saved.selected_container_idx;
+        motion::cancel(app, Property::ViewportY);
+        tween::set_property(app, Property::ViewportY, saved.viewport_y);
+        app.viewport.target_y = saved.viewport_y;
+        for (c_idx, scroll) in saved.row_scroll.iter().enumerate() {
+            motion::cancel(app, Property::RowScroll(c_idx));
+            tween::set_property(app, Property::RowScroll(c_idx), *scroll);
+            app.containers[c_idx].scroll_target = *scroll;
+        }
+        if let Some(id) = saved.focus {
+            app.focus.set(id);
+        }
+    }
+
+    // Dims the home rows, grows the artwork out of the selected tile and fades the page content in
+    fn render(&self, app: &App, progress: f32, window_size: &(u32, u32)) {
+        app_gl::draw_color_rect(app, &app_gl::screen_rect(window_size), [0., 0., 0., 0.85 * progress], window_size);
+
+        let artwork_rect = transition_rect(self, progress, window_size);
+        match self.hero_texture_id.or(self.tile_texture_id) {
+            Some(texture_id) => app_gl::draw_image(app, &artwork_rect, texture_id, 1., window_size),
+            None => skeleton::draw(app, &artwork_rect, 1., window_size),
+        }
+
+        // Content waits for the artwork to be mostly in place
+        let alpha = util::clamp(progress * 2. - 1., 0., 1.);
+        app_gl::draw_text(app, &title_rect(self, window_size), self.title.texture_id, alpha, window_size);
+        app_gl::draw_text(
+            app,
+            &metadata_rect(self, window_size),
+            self.metadata.texture_id,
+            alpha * 0.7,
+            window_size,
+        );
+        for (line_idx, line) in self.description.iter().enumerate() {
+            app_gl::draw_text(
+                app,
+                &description_rect(self, line_idx, window_size),
+                line.texture_id,
+                alpha * 0.9,
+                window_size,
+            );
+        }
+
+        for (button_idx, label) in self.buttons.iter().enumerate() {
+            let rect = button_rect(button_idx);
+            let color = if button_idx == self.focused_button {
+                [0.2, 0.45, 0.9, alpha]
+            } else {
+                [1., 1., 1., 0.15 * alpha]
+            };
+            app_gl::draw_color_rect(app, &rect, color, window_size);
+
+            let label_rect = Rect {
+                width: label.width as f32,
+                height: label.height as f32,
+                ..rect
+            };
+            app_gl::draw_text(app, &label_rect, label.texture_id, alpha, window_size);
+        }
+    }
+}
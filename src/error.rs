@@ -0,0 +1,100 @@
+use sfml::window::mouse::Button;
+use sfml::window::Event;
+
+use crate::input::Action;
+use crate::layout::Rect;
+use crate::screen::{Screen, ScreenKind, Transition};
+use crate::{app_gl, App};
+
+static HEADING_CHAR_SIZE: isize = 56 * 32;
+static MESSAGE_CHAR_SIZE: isize = 28 * 32;
+static HINT_CHAR_SIZE: isize = 28 * 32;
+// Long messages (e.g. from reqwest) are cut rather than wrapped
+static MESSAGE_MAX_CHARS: usize = 90;
+
+// Full screen notice for failures the user should know about, dismissed with Select or Back
+pub struct ErrorScreen {
+    heading: app_gl::RenderedImage,
+    message: app_gl::RenderedImage,
+    hint: app_gl::RenderedImage,
+}
+
+impl Drop for ErrorScreen {
+    fn drop(&mut self) {
+        app_gl::release_texture(self.heading.texture_id);
+        app_gl::release_texture(self.message.texture_id);
+        app_gl::release_texture(self.hint.texture_id);
+    }
+}
+
+impl ErrorScreen {
+    pub fn new(message: &str) -> Self {
+        let message: String = message.chars().take(MESSAGE_MAX_CHARS).collect();
+        ErrorScreen {
+            heading: app_gl::render_sized_text_to_texture("Something went wrong", HEADING_CHAR_SIZE),
+            message: app_gl::render_sized_text_to_texture(&message, MESSAGE_CHAR_SIZE),
+            hint: app_gl::render_sized_text_to_texture("Press Back to continue", HINT_CHAR_SIZE),
+        }
+    }
+}
+
+fn centered_rect(image: &app_gl::RenderedImage, y: f32, window_size: &(u32, u32)) -> Rect {
+    Rect {
+        x: window_size.0 as f32 / 2.,
+        y,
+        width: image.width as f32,
+        height: image.height as f32,
+    }
+}
+
+impl Screen for ErrorScreen {
+    fn kind(&self) -> ScreenKind {
+        ScreenKind::Error
+    }
+
+    fn handle_action(&mut self, _app: &mut App, action: Action) -> Transition {
+        match action {
+            Action::Back | Action::Select => Transition::Pop,
+            _ => Transition::None,
+        }
+    }
+
+    fn map_mouse_event(&mut self, _app: &mut App, event: &Event, _window_size: &(u32, u32)) -> Option<Action> {
+        match *event {
+            Event::MouseButtonReleased { button: Button::LEFT, .. } => Some(Action::Back),
+            _ => None,
+        }
+    }
+
+    fn render(&self, app: &App, progress: f32, window_size: &(u32, u32)) {
+        app_gl::draw_color_rect(
+            app,
+            &app_gl::screen_rect(window_size),
+            [0.05, 0.02, 0.02, 0.9 * progress],
+            window_size,
+        );
+
+        let center_y = window_size.1 as f32 / 2.;
+        app_gl::draw_text(
+            app,
+            &centered_rect(&self.heading, center_y + 80., window_size),
+            self.heading.texture_id,
+            progress,
+            window_size,
+        );
+        app_gl::draw_text(
+            app,
+            &centered_rect(&self.message, center_y, window_size),
+            self.message.texture_id,
+            progress * 0.8,
+            window_size,
+        );
+        app_gl::draw_text(
+            app,
+            &centered_rect(&self.hint, center_y - 120., window_size),
+            self.hint.texture_id,
+            progress * 0.6,
+            window_size,
+        );
+    }
+}
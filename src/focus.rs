@@ -0,0 +1,127 @@
+use crate::layout::Rect;
+
+// Anything that can hold focus on screen
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FocusId {
+    Tile(usize, usize),
+    Button(usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// How vertical moves pick a tile in the destination row
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FocusMode {
+    // Return to the tile last focused in that row
+    RememberPerRow,
+    // Take the tile closest to the current on-screen column
+    NearestColumn,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Focusable {
+    pub id: FocusId,
+    pub rect: Rect,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FocusChanged {
+    pub from: Option<FocusId>,
+    pub to: FocusId,
+}
+
+// Distance along the direction of travel and across it, or None if the candidate lies behind
+fn distances(from: &Rect, to: &Rect, direction: Direction) -> Option<(f32, f32)> {
+    let dx = to.x - from.x;
+    let dy = to.y - from.y;
+    // Layout space has y growing upwards
+    let (primary, secondary) = match direction {
+        Direction::Left => (-dx, dy),
+        Direction::Right => (dx, dy),
+        Direction::Up => (dy, dx),
+        Direction::Down => (-dy, dx),
+    };
+
+    if primary <= 0. {
+        None
+    } else {
+        Some((primary, secondary.abs()))
+    }
+}
+
+// Whether two rects overlap across the direction of travel
+fn in_beam(from: &Rect, to: &Rect, direction: Direction) -> bool {
+    match direction {
+        Direction::Left | Direction::Right => (to.y - from.y).abs() < (to.height + from.height) / 2.,
+        Direction::Up | Direction::Down => (to.x - from.x).abs() < (to.width + from.width) / 2.,
+    }
+}
+
+// Picks the closest candidate in the given direction. Sideways moves stay within the same band,
+// vertical moves go to the nearest band and then to the candidate closest across it.
+pub fn find_next(from: &Rect, direction: Direction, candidates: &[Focusable]) -> Option<FocusId> {
+    let horizontal = direction == Direction::Left || direction == Direction::Right;
+    let scored: Vec<(FocusId, f32, f32)> = candidates
+        .iter()
+        .filter(|e| !horizontal || in_beam(from, &e.rect, direction))
+        .filter_map(|e| distances(from, &e.rect, direction).map(|(primary, secondary)| (e.id, primary, secondary)))
+        .collect();
+
+    let nearest_primary = scored.iter().map(|e| e.1).fold(f32::INFINITY, f32::min);
+    let band = if horizontal { f32::INFINITY } else { from.height / 2. };
+    scored
+        .iter()
+        .filter(|e| horizontal || e.1 <= nearest_primary + band)
+        .min_by(|a, b| {
+            let a_score = if horizontal { a.1 } else { a.2 };
+            let b_score = if horizontal { b.1 } else { b.2 };
+            a_score.partial_cmp(&b_score).unwrap()
+        })
+        .map(|e| e.0)
+}
+
+#[derive(Debug)]
+pub struct FocusManager {
+    pub mode: FocusMode,
+    current: Option<FocusId>,
+    events: Vec<FocusChanged>,
+}
+
+impl Default for FocusManager {
+    fn default() -> Self {
+        FocusManager {
+            mode: FocusMode::RememberPerRow,
+            current: None,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl FocusManager {
+    pub fn current(&self) -> Option<FocusId> {
+        self.current
+    }
+
+    pub fn set(&mut self, id: FocusId) {
+        if self.current == Some(id) {
+            return;
+        }
+
+        self.events.push(FocusChanged { from: self.current, to: id });
+        self.current = Some(id);
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+    }
+
+    pub fn drain_events(&mut self) -> Vec<FocusChanged> {
+        std::mem::take(&mut self.events)
+    }
+}
@@ -0,0 +1,91 @@
+// Bonus for each query character found directly after the previous match
+static CONSECUTIVE_BONUS: i32 = 5;
+// Bonus for a match at the start of a word, e.g. "tm" in "The Mandalorian"
+static WORD_START_BONUS: i32 = 8;
+static PREFIX_BONUS: i32 = 10;
+// Skipped characters count against a candidate, but only up to this many
+static MAX_GAP_PENALTY: i32 = 15;
+
+// Case-insensitive subsequence match. Returns None when the query characters don't all appear in order,
+// otherwise a score where higher means a tighter match.
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    let mut score = 0;
+    let mut gaps = 0;
+    let mut q_idx = 0;
+    let mut last_match: Option<usize> = None;
+    for (c_idx, c) in candidate.iter().enumerate() {
+        if q_idx == query.len() {
+            break;
+        }
+        if *c != query[q_idx] {
+            if last_match.is_some() {
+                gaps += 1;
+            }
+            continue;
+        }
+
+        score += 1;
+        if last_match.is_some() && last_match == c_idx.checked_sub(1) {
+            score += CONSECUTIVE_BONUS;
+        }
+        if c_idx == 0 || !candidate[c_idx - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        last_match = Some(c_idx);
+        q_idx += 1;
+    }
+
+    if q_idx < query.len() {
+        return None;
+    }
+
+    // The query has its spaces dropped, so "the m" has to be compared with the candidate's too
+    let compact: Vec<char> = candidate.iter().filter(|c| !c.is_whitespace()).copied().collect();
+    if compact.starts_with(&query) {
+        score += PREFIX_BONUS;
+    }
+    Some(score - gaps.min(MAX_GAP_PENALTY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_rank_above_later_matches() {
+        assert!(score("man", "Mandalorian") > score("man", "Iron Man"));
+        // Spaces in the query don't lose the prefix bonus
+        let expected = 4 + 2 * CONSECUTIVE_BONUS + 2 * WORD_START_BONUS + PREFIX_BONUS - 1;
+        assert_eq!(score("the m", "The Mandalorian"), Some(expected));
+        assert_eq!(score("THE M", "the mandalorian"), Some(expected));
+    }
+
+    #[test]
+    fn word_starts_rank_above_mid_word_matches() {
+        assert!(score("tm", "The Mandalorian") > score("tm", "Batman"));
+    }
+
+    #[test]
+    fn gaps_count_against_a_match_up_to_a_limit() {
+        assert!(score("abc", "abcx") > score("abc", "axbxc"));
+        assert!(score("abc", "xaxbxc") > score("abc", "xaxxxxbxxxxc"));
+        let long = format!("a{}c", "b".repeat(30));
+        let longer = format!("a{}c", "b".repeat(50));
+        assert_eq!(score("ac", &long), score("ac", &longer));
+    }
+
+    #[test]
+    fn non_matches_have_no_score() {
+        assert_eq!(score("xyz", "The Mandalorian"), None);
+        assert_eq!(score("nam", "Man"), None);
+        assert_eq!(score("mann", "Man"), None);
+        assert_eq!(score("", "Man"), None);
+        assert_eq!(score("  ", "Man"), None);
+    }
+}
@@ -0,0 +1,209 @@
+use sfml::window::joystick::{self, Axis};
+use sfml::window::Event;
+use std::collections::{HashMap, HashSet};
+
+use crate::input::Action;
+
+// Axis positions are reported in [-100, 100]; anything closer to rest than this is ignored
+static DEFAULT_DEAD_ZONE: f32 = 35.;
+
+#[derive(Debug, Copy, Clone)]
+struct AxisMapping {
+    axis: Axis,
+    negative: Action,
+    positive: Action,
+}
+
+// Translates joystick events into navigation actions, firing once per push of a stick or d-pad
+#[derive(Debug)]
+pub struct Gamepad {
+    pub dead_zone: f32,
+    buttons: HashMap<u32, Action>,
+    axes: Vec<AxisMapping>,
+    axis_directions: HashMap<(u32, Axis), i8>,
+    connected: HashSet<u32>,
+}
+
+impl Default for Gamepad {
+    fn default() -> Self {
+        // Button indices follow the XInput layout SFML reports for common controllers
+        let buttons = [
+            (0, Action::Select),
+            (1, Action::Back),
+            (3, Action::Search),
+            (6, Action::Settings),
+            (7, Action::Pause),
+        ];
+        let axes = vec![
+            // Left analog stick, y grows downwards
+            AxisMapping {
+                axis: Axis::X,
+                negative: Action::Left,
+                positive: Action::Right,
+            },
+            AxisMapping {
+                axis: Axis::Y,
+                negative: Action::Up,
+                positive: Action::Down,
+            },
+            // D-pad, reported by SFML as the point-of-view hat with y growing upwards
+            AxisMapping {
+                axis: Axis::POV_X,
+                negative: Action::Left,
+                positive: Action::Right,
+            },
+            AxisMapping {
+                axis: Axis::POV_Y,
+                negative: Action::Down,
+                positive: Action::Up,
+            },
+        ];
+
+        Gamepad {
+            dead_zone: DEFAULT_DEAD_ZONE,
+            buttons: buttons.iter().cloned().collect(),
+            axes,
+            axis_directions: HashMap::new(),
+            connected: HashSet::new(),
+        }
+    }
+}
+
+impl Gamepad {
+    // Picks up joysticks that were already plugged in before the window started receiving events
+    pub fn scan_connected(&mut self) {
+        joystick::update();
+        for joystick_id in 0..joystick::COUNT {
+            if joystick::is_connected(joystick_id) {
+                self.connect(joystick_id);
+            }
+        }
+    }
+
+    pub fn is_connected(&self, joystick_id: u32) -> bool {
+        self.connected.contains(&joystick_id)
+    }
+
+    fn connect(&mut self, joystick_id: u32) {
+        self.connected.insert(joystick_id);
+    }
+
+    fn disconnect(&mut self, joystick_id: u32) {
+        self.connected.remove(&joystick_id);
+        self.axis_directions.retain(|(id, _), _| *id != joystick_id);
+    }
+
+    pub fn map_event(&mut self, event: &Event) -> Option<Action> {
+        match *event {
+            Event::JoystickConnected { joystickid } => {
+                self.connect(joystickid);
+                None
+            }
+            Event::JoystickDisconnected { joystickid } => {
+                self.disconnect(joystickid);
+                None
+            }
+            Event::JoystickButtonPressed { joystickid, button } => {
+                self.connect(joystickid);
+                self.buttons.get(&button).copied()
+            }
+            Event::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            } => {
+                self.connect(joystickid);
+                self.map_axis(joystickid, axis, position)
+            }
+            _ => None,
+        }
+    }
+
+    fn map_axis(&mut self, joystick_id: u32, axis: Axis, position: f32) -> Option<Action> {
+        let mapping = *self.axes.iter().find(|e| e.axis == axis)?;
+        let direction: i8 = if position > self.dead_zone {
+            1
+        } else if position < -self.dead_zone {
+            -1
+        } else {
+            0
+        };
+
+        let previous = self.axis_directions.insert((joystick_id, axis), direction).unwrap_or(0);
+        if direction == previous {
+            return None;
+        }
+
+        match direction {
+            1 => Some(mapping.positive),
+            -1 => Some(mapping.negative),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(axis: Axis, position: f32) -> Event {
+        Event::JoystickMoved {
+            joystickid: 0,
+            axis,
+            position,
+        }
+    }
+
+    #[test]
+    fn buttons_map_to_actions() {
+        let mut gamepad = Gamepad::default();
+        let pressed = |button| Event::JoystickButtonPressed { joystickid: 0, button };
+        assert_eq!(gamepad.map_event(&pressed(0)), Some(Action::Select));
+        assert_eq!(gamepad.map_event(&pressed(1)), Some(Action::Back));
+        assert_eq!(gamepad.map_event(&pressed(7)), Some(Action::Pause));
+        assert_eq!(gamepad.map_event(&pressed(12)), None);
+        assert!(gamepad.is_connected(0));
+    }
+
+    #[test]
+    fn stick_fires_once_per_push_outside_the_dead_zone() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 20.)), None);
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 60.)), Some(Action::Right));
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 100.)), None);
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 10.)), None);
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 60.)), Some(Action::Right));
+        assert_eq!(gamepad.map_event(&moved(Axis::X, -60.)), Some(Action::Left));
+        assert_eq!(gamepad.map_event(&moved(Axis::Y, 60.)), Some(Action::Down));
+    }
+
+    #[test]
+    fn dead_zone_is_adjustable() {
+        let mut gamepad = Gamepad {
+            dead_zone: 80.,
+            ..Default::default()
+        };
+        assert_eq!(gamepad.map_event(&moved(Axis::Y, -60.)), None);
+        assert_eq!(gamepad.map_event(&moved(Axis::Y, -90.)), Some(Action::Up));
+    }
+
+    #[test]
+    fn pov_hat_has_y_growing_upwards() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(gamepad.map_event(&moved(Axis::POV_Y, 100.)), Some(Action::Up));
+        assert_eq!(gamepad.map_event(&moved(Axis::POV_Y, 0.)), None);
+        assert_eq!(gamepad.map_event(&moved(Axis::POV_Y, -100.)), Some(Action::Down));
+        assert_eq!(gamepad.map_event(&moved(Axis::POV_X, -100.)), Some(Action::Left));
+    }
+
+    #[test]
+    fn disconnect_forgets_held_directions() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 60.)), Some(Action::Right));
+        assert_eq!(gamepad.map_event(&Event::JoystickDisconnected { joystickid: 0 }), None);
+        assert!(!gamepad.is_connected(0));
+        // A stick still held when the pad comes back counts as a fresh push
+        assert_eq!(gamepad.map_event(&Event::JoystickConnected { joystickid: 0 }), None);
+        assert_eq!(gamepad.map_event(&moved(Axis::X, 60.)), Some(Action::Right));
+    }
+}
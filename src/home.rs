@@ -0,0 +1,106 @@
+use sfml::window::Event;
+
+use crate::detail::Detail;
+use crate::input::Action;
+use crate::row_style::RowStyle;
+use crate::screen::{Screen, ScreenKind, Transition};
+use crate::search::Search;
+use crate::settings::Settings;
+use crate::tween::Property;
+use crate::{animation, app_gl, focus, layout, motion, mouse, skeleton, video, App};
+
+// Seconds a carousel rests on a banner before moving on by itself
+static CAROUSEL_INTERVAL: f32 = 6.;
+
+// Rows of tiles for every loaded container; the root of the screen stack
+pub struct Home;
+
+// Moves hero carousels on to their next banner, wrapping back to the first after the last
+fn advance_carousels(app: &mut App, dt: f32) {
+    for c_idx in 0..app.containers.len() {
+        let container = &mut app.containers[c_idx];
+        if container.style != RowStyle::Hero || container.images.len() < 2 {
+            continue;
+        }
+
+        container.auto_advance_timer += dt;
+        if container.auto_advance_timer < CAROUSEL_INTERVAL {
+            continue;
+        }
+
+        container.auto_advance_timer = 0.;
+        let t_idx = (container.desired_selected_tile_idx as usize + 1) % container.images.len();
+        if c_idx == app.selected_container_idx {
+            crate::focus_tile(app, c_idx, t_idx);
+        } else {
+            container.desired_selected_tile_idx = t_idx as f32;
+            let row_scroll = layout::row_scroll_target(app, c_idx, t_idx);
+            app.containers[c_idx].scroll_target = row_scroll;
+            motion::animate_to(app, Property::RowScroll(c_idx), row_scroll);
+        }
+    }
+}
+
+impl Screen for Home {
+    fn kind(&self) -> ScreenKind {
+        ScreenKind::Home
+    }
+
+    fn is_opaque(&self) -> bool {
+        true
+    }
+
+    fn handle_action(&mut self, app: &mut App, action: Action) -> Transition {
+        self.handle_repeated_action(app, action, 1)
+    }
+
+    fn handle_repeated_action(&mut self, app: &mut App, action: Action, count: i32) -> Transition {
+        if let Some(direction) = crate::action_direction(action) {
+            crate::navigate(app, direction, count);
+            return Transition::None;
+        }
+
+        match action {
+            Action::Select => match app.focus.current() {
+                Some(focus::FocusId::Tile(c_idx, t_idx)) => match Detail::open(app, c_idx, t_idx) {
+                    Some(detail) => Transition::Push(Box::new(detail)),
+                    None => Transition::None,
+                },
+                _ => Transition::None,
+            },
+            Action::Settings => Transition::Push(Box::new(Settings::new(app))),
+            Action::Search => Transition::Push(Box::new(Search::default())),
+            _ => Transition::None,
+        }
+    }
+
+    fn map_mouse_event(&mut self, app: &mut App, event: &Event, window_size: &(u32, u32)) -> Option<Action> {
+        mouse::map_event(app, event, window_size)
+    }
+
+    fn update(&mut self, app: &mut App, dt: f32) {
+        advance_carousels(app, dt);
+        skeleton::tick(app, dt);
+        animation::tick(app, dt);
+        video::tick(app, dt);
+        crate::play_row_entrances(app);
+
+        // Focus the selected row's remembered tile once the row has one
+        if app.focus.current().is_none() {
+            if let Some(container) = app.containers.get(app.selected_container_idx) {
+                let t_idx = container.desired_selected_tile_idx as usize;
+                if t_idx < container.images.len() {
+                    app.focus.set(focus::FocusId::Tile(app.selected_container_idx, t_idx));
+                }
+            }
+        }
+
+        for event in app.focus.drain_events() {
+            crate::on_focus_changed(app, event);
+        }
+    }
+
+    fn render(&self, app: &App, _progress: f32, window_size: &(u32, u32)) {
+        app_gl::render_home(app, window_size);
+    }
+}
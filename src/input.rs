@@ -0,0 +1,237 @@
+use sfml::window::Key;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+    Quit,
+    Pause,
+    SlowMotion,
+    Step,
+    Settings,
+    Search,
+    Mute,
+}
+
+static ACTION_NAMES: [(&str, Action); 13] = [
+    ("Up", Action::Up),
+    ("Down", Action::Down),
+    ("Left", Action::Left),
+    ("Right", Action::Right),
+    ("Select", Action::Select),
+    ("Back", Action::Back),
+    ("Quit", Action::Quit),
+    ("Pause", Action::Pause),
+    ("SlowMotion", Action::SlowMotion),
+    ("Step", Action::Step),
+    ("Settings", Action::Settings),
+    ("Search", Action::Search),
+    ("Mute", Action::Mute),
+];
+
+fn action_from_name(name: &str) -> Option<Action> {
+    ACTION_NAMES.iter().find(|(n, _)| *n == name).map(|(_, a)| *a)
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    let key = match name {
+        "A" => Key::A,
+        "B" => Key::B,
+        "C" => Key::C,
+        "D" => Key::D,
+        "E" => Key::E,
+        "F" => Key::F,
+        "G" => Key::G,
+        "H" => Key::H,
+        "I" => Key::I,
+        "J" => Key::J,
+        "K" => Key::K,
+        "L" => Key::L,
+        "M" => Key::M,
+        "N" => Key::N,
+        "O" => Key::O,
+        "P" => Key::P,
+        "Q" => Key::Q,
+        "R" => Key::R,
+        "S" => Key::S,
+        "T" => Key::T,
+        "U" => Key::U,
+        "V" => Key::V,
+        "W" => Key::W,
+        "X" => Key::X,
+        "Y" => Key::Y,
+        "Z" => Key::Z,
+        "Num0" => Key::NUM0,
+        "Num1" => Key::NUM1,
+        "Num2" => Key::NUM2,
+        "Num3" => Key::NUM3,
+        "Num4" => Key::NUM4,
+        "Num5" => Key::NUM5,
+        "Num6" => Key::NUM6,
+        "Num7" => Key::NUM7,
+        "Num8" => Key::NUM8,
+        "Num9" => Key::NUM9,
+        "Numpad0" => Key::NUMPAD0,
+        "Numpad1" => Key::NUMPAD1,
+        "Numpad2" => Key::NUMPAD2,
+        "Numpad3" => Key::NUMPAD3,
+        "Numpad4" => Key::NUMPAD4,
+        "Numpad5" => Key::NUMPAD5,
+        "Numpad6" => Key::NUMPAD6,
+        "Numpad7" => Key::NUMPAD7,
+        "Numpad8" => Key::NUMPAD8,
+        "Numpad9" => Key::NUMPAD9,
+        "Up" => Key::UP,
+        "Down" => Key::DOWN,
+        "Left" => Key::LEFT,
+        "Right" => Key::RIGHT,
+        "Enter" => Key::ENTER,
+        "Space" => Key::SPACE,
+        "Escape" => Key::ESCAPE,
+        "Backspace" => Key::BACKSPACE,
+        "Tab" => Key::TAB,
+        "Home" => Key::HOME,
+        "End" => Key::END,
+        "PageUp" => Key::PAGEUP,
+        "PageDown" => Key::PAGEDOWN,
+        "Period" => Key::PERIOD,
+        "Comma" => Key::COMMA,
+        "Slash" => Key::SLASH,
+        "Pause" => Key::PAUSE,
+        "Menu" => Key::MENU,
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+// Maps physical keys onto actions; an action may be bound to any number of keys
+#[derive(Debug)]
+pub struct Bindings {
+    keys: HashMap<Key, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let defaults = [
+            (Key::W, Action::Up),
+            (Key::UP, Action::Up),
+            (Key::S, Action::Down),
+            (Key::DOWN, Action::Down),
+            (Key::A, Action::Left),
+            (Key::LEFT, Action::Left),
+            (Key::D, Action::Right),
+            (Key::RIGHT, Action::Right),
+            (Key::ENTER, Action::Select),
+            (Key::SPACE, Action::Select),
+            (Key::ESCAPE, Action::Back),
+            (Key::BACKSPACE, Action::Back),
+            (Key::Q, Action::Quit),
+            (Key::P, Action::Pause),
+            (Key::M, Action::SlowMotion),
+            (Key::PERIOD, Action::Step),
+            (Key::TAB, Action::Settings),
+            (Key::SLASH, Action::Search),
+            (Key::N, Action::Mute),
+        ];
+
+        Bindings {
+            keys: defaults.iter().cloned().collect(),
+        }
+    }
+}
+
+impl Bindings {
+    // Loads a bindings file of the form { "Up": ["W", "Up"], ... }. Actions missing from the file keep their default keys.
+    pub fn load_from_disk(path: &str) -> Result<Bindings, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open bindings file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read bindings file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed bindings file {}: {:?}", path, err))?;
+        let entries = data.as_object().ok_or(format!("Bindings file {} should be an object", path))?;
+
+        let mut bindings = Bindings::default();
+        for (action_name, key_names) in entries {
+            let action = action_from_name(action_name).ok_or(format!("Unknown action in bindings: {}", action_name))?;
+            let key_names = key_names
+                .as_array()
+                .ok_or(format!("Keys for action {} should be an array", action_name))?;
+
+            bindings.keys.retain(|_, a| *a != action);
+            for key_name in key_names {
+                let key_name = key_name.as_str().unwrap_or("");
+                let key = key_from_name(key_name).ok_or(format!("Unknown key in bindings: {:?}", key_name))?;
+                bindings.keys.insert(key, action);
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        self.keys.get(&key).copied()
+    }
+}
+
+// Keys that type into a text field rather than trigger their binding while a screen captures text
+pub fn is_text_key(key: Key) -> bool {
+    (Key::A..=Key::Z).contains(&key) || (Key::NUM0..=Key::NUM9).contains(&key) || key == Key::SPACE || key == Key::BACKSPACE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, contents: &str) -> Result<Bindings, String> {
+        let path = std::env::temp_dir().join(format!("sfml_example_bindings_{}_{}.json", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let bindings = Bindings::load_from_disk(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        bindings
+    }
+
+    #[test]
+    fn shipped_bindings_match_the_defaults() {
+        let bindings = Bindings::load_from_disk("res/bindings.json").unwrap();
+        assert_eq!(bindings.keys, Bindings::default().keys);
+    }
+
+    #[test]
+    fn rebinding_an_action_replaces_its_keys() {
+        let bindings = load("rebind", r#"{ "Up": ["I", "PageUp"] }"#).unwrap();
+        assert_eq!(bindings.action_for(Key::I), Some(Action::Up));
+        assert_eq!(bindings.action_for(Key::PAGEUP), Some(Action::Up));
+        assert_eq!(bindings.action_for(Key::W), None);
+        // Actions the file leaves out keep their defaults
+        assert_eq!(bindings.action_for(Key::S), Some(Action::Down));
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_rejected() {
+        assert!(load("action", r#"{ "Jump": ["J"] }"#).unwrap_err().contains("Unknown action"));
+        assert!(load("key", r#"{ "Up": ["Hyper"] }"#).unwrap_err().contains("Unknown key"));
+        assert!(load("shape", r#"{ "Up": "W" }"#).unwrap_err().contains("should be an array"));
+        assert!(load("json", "{ Up").unwrap_err().contains("Malformed"));
+    }
+}
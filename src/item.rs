@@ -0,0 +1,76 @@
+// Program, series or collection entry from the content API, with the fields the UI displays
+#[derive(Debug, Clone)]
+pub struct Item {
+    // Content, series or collection id, whichever the entry carries
+    pub id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub rating: Option<String>,
+    pub release_year: Option<i64>,
+    pub tile_url: String,
+    pub raw: serde_json::Value,
+}
+
+// Text and image entries are keyed by the item's kind (series, program, collection, default); take whichever is present
+fn first_of_kind<'a>(value: &'a serde_json::Value, path: &[&str]) -> Option<&'a str> {
+    value.as_object()?.values().find_map(|kind| {
+        let mut node = kind;
+        for key in path {
+            node = &node[*key];
+        }
+        node.as_str()
+    })
+}
+
+impl Item {
+    pub fn from_json_value(item: &serde_json::Value) -> Self {
+        let title = first_of_kind(&item["text"]["title"]["full"], &["default", "content"]).unwrap_or("");
+        let description = first_of_kind(&item["text"]["description"]["medium"], &["default", "content"])
+            .or_else(|| first_of_kind(&item["text"]["description"]["brief"], &["default", "content"]))
+            .unwrap_or("");
+
+        let id = ["contentId", "seriesId", "collectionId"].iter().find_map(|key| item[*key].as_str());
+
+        Item {
+            id: id.map(|e| e.to_string()),
+            title: title.to_string(),
+            description: description.to_string(),
+            rating: item["ratings"][0]["value"].as_str().map(|e| e.to_string()),
+            release_year: item["releases"][0]["releaseYear"].as_i64(),
+            tile_url: crate::get_item_image_url_from_json_value(item),
+            raw: item.clone(),
+        }
+    }
+
+    // Url of an image variant such as ("hero_collection", "1.78") or ("tile", "0.71")
+    pub fn image_url(&self, kind: &str, aspect_ratio: &str) -> Option<String> {
+        first_of_kind(&self.raw["image"][kind][aspect_ratio], &["default", "url"]).map(|e| e.to_string())
+    }
+
+    // BlurHash or tiny data url preview published alongside an image variant
+    pub fn image_preview(&self, kind: &str, aspect_ratio: &str) -> Option<String> {
+        let variant = &self.raw["image"][kind][aspect_ratio];
+        first_of_kind(variant, &["default", "blurHash"])
+            .or_else(|| first_of_kind(variant, &["default", "preview"]))
+            .map(|e| e.to_string())
+    }
+
+    // Large artwork for the detail page, falling back to the tile image
+    pub fn hero_url(&self) -> String {
+        self.image_url("hero_collection", "1.78")
+            .or_else(|| self.image_url("background", "1.78"))
+            .unwrap_or_else(|| self.tile_url.clone())
+    }
+
+    // Single line of secondary information, e.g. "2019  -  TV-PG"
+    pub fn metadata_line(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(year) = self.release_year {
+            parts.push(year.to_string());
+        }
+        if let Some(rating) = &self.rating {
+            parts.push(rating.to_string());
+        }
+        parts.join("  -  ")
+    }
+}
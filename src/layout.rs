@@ -0,0 +1,323 @@
+use crate::row_style::{RowAlign, RowStyle};
+use crate::screen::ScreenKind;
+use crate::{scroll, util, App};
+
+// Vertical gap between the lines of a wrapped grid
+static GRID_LINE_GAP: f32 = 40.;
+
+// Centered rectangle in window space with the origin at the bottom left, matching the GL projection
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        (x - self.x).abs() <= self.width / 2. && (y - self.y).abs() <= self.height / 2.
+    }
+
+    // Whether any of the rect falls inside the window
+    pub fn is_on_screen(&self, window_size: &(u32, u32)) -> bool {
+        self.x + self.width / 2. >= 0.
+            && self.x - self.width / 2. <= window_size.0 as f32
+            && self.y + self.height / 2. >= 0.
+            && self.y - self.height / 2. <= window_size.1 as f32
+    }
+}
+
+// Converts a mouse position (origin top left) into layout space
+pub fn from_window_coords(x: i32, y: i32, window_size: &(u32, u32)) -> (f32, f32) {
+    (x as f32, window_size.1 as f32 - y as f32)
+}
+
+fn origin(window_size: &(u32, u32)) -> (f32, f32) {
+    (window_size.0 as f32 / 2. - 550., window_size.1 as f32 / 2. + 350.)
+}
+
+// Artwork size of a row style before focus scaling, scaled so landscape tiles match the configured tile size
+pub fn style_tile_size(app: &App, style: RowStyle) -> (f32, f32) {
+    let (width, height) = style.tile_size();
+    let base = RowStyle::Landscape.tile_size();
+    (width * app.tile_size.0 / base.0, height * app.tile_size.1 / base.1)
+}
+
+fn tile_size(app: &App, c_idx: usize) -> (f32, f32) {
+    style_tile_size(app, app.containers[c_idx].style)
+}
+
+// Height of one line of tiles, keeping the spacing of landscape rows
+fn line_height(app: &App, c_idx: usize) -> f32 {
+    app.row_height * tile_size(app, c_idx).1 / app.tile_size.1
+}
+
+// Column and line a tile occupies within its row; scrolling rows keep everything on line 0
+fn tile_cell(app: &App, c_idx: usize, t_idx: usize) -> (usize, usize) {
+    match app.containers[c_idx].style.columns() {
+        Some(columns) => (t_idx % columns, t_idx / columns),
+        None => (t_idx, 0),
+    }
+}
+
+pub fn row_height(app: &App, c_idx: usize) -> f32 {
+    let lines = app.containers[c_idx].lines;
+    line_height(app, c_idx) * lines + GRID_LINE_GAP * (lines - 1.)
+}
+
+// Distance of a row's title from the top of the page
+pub fn row_offset(app: &App, c_idx: usize) -> f32 {
+    (0..c_idx).map(|c| app.title_height + row_height(app, c)).sum()
+}
+
+pub fn tile_spacing(app: &App, c_idx: usize) -> f32 {
+    app.containers[c_idx].style.tile_spacing() * app.tile_size.0 / RowStyle::Landscape.tile_size().0
+}
+
+// Distances from the top of the page to the top and bottom of the line holding the tile
+fn focus_band(app: &App, c_idx: usize, t_idx: usize) -> (f32, f32) {
+    let (_, line) = tile_cell(app, c_idx, t_idx);
+    let line_height = line_height(app, c_idx);
+    let top = row_offset(app, c_idx) + line as f32 * (line_height + GRID_LINE_GAP);
+    (top, top + app.title_height - app.row_height / 2. + line_height)
+}
+
+// Viewport position for the tile's line under the home screen's scroll policy
+pub fn focus_viewport_y(app: &App, c_idx: usize, t_idx: usize) -> f32 {
+    let window_size = &app.window_size;
+    let view = scroll::View {
+        top: origin(window_size).1 - window_size.1 as f32,
+        height: window_size.1 as f32,
+    };
+    let page = app.scroll.for_screen(ScreenKind::Home);
+    scroll::scroll_to(&page, app.viewport.target_y, focus_band(app, c_idx, t_idx), &view)
+}
+
+// Tiles that fit whole between the rows' shared left edge and the right of the window
+fn visible_tiles(app: &App, c_idx: usize, window_size: &(u32, u32)) -> f32 {
+    let space = window_size.0 as f32 - (origin(window_size).0 - app.tile_size.0 / 2.);
+    ((space - tile_size(app, c_idx).0) / tile_spacing(app, c_idx)).floor().max(0.) + 1.
+}
+
+// Distance in tiles from the row scroll position to the focused tile. Edge scrolling has no fixed distance,
+// so it uses the middle of the visible tiles.
+fn focus_offset(app: &App, c_idx: usize, window_size: &(u32, u32)) -> f32 {
+    let container = &app.containers[c_idx];
+    match container.scrolling.align {
+        RowAlign::Left => 0.,
+        RowAlign::Edge => (visible_tiles(app, c_idx, window_size) - 1.) / 2.,
+        RowAlign::Centered => {
+            let size = tile_size(app, c_idx);
+            (window_size.0 as f32 / 2. - (origin(window_size).0 - app.tile_size.0 / 2.) - size.0 / 2.) / tile_spacing(app, c_idx)
+        }
+    }
+}
+
+// Row scroll position at rest while the tile is focused; grids never scroll
+pub fn row_scroll_target(app: &App, c_idx: usize, t_idx: usize) -> f32 {
+    let container = &app.containers[c_idx];
+    if container.style.columns().is_some() {
+        return 0.;
+    }
+
+    let window_size = &app.window_size;
+    let current = container.scroll_target;
+    let mut t = t_idx as f32;
+    // Wrapping rows head for whichever repeat of the tile is nearest, so stepping past the end keeps going the same way
+    if container.scrolling.wrap {
+        let len = container.images.len().max(1) as f32;
+        let reference = current + focus_offset(app, c_idx, window_size);
+        t += len * ((reference - t) / len).round();
+    }
+
+    match container.scrolling.align {
+        RowAlign::Left => t,
+        RowAlign::Edge => current.max(t - (visible_tiles(app, c_idx, window_size) - 1.)).min(t),
+        RowAlign::Centered => t - focus_offset(app, c_idx, window_size),
+    }
+}
+
+// Lowest and highest scroll positions a row can be dragged to, or None when it wraps around
+pub fn row_scroll_range(app: &App, c_idx: usize) -> Option<(f32, f32)> {
+    let container = &app.containers[c_idx];
+    if container.scrolling.wrap {
+        return None;
+    }
+
+    let last = container.images.len().saturating_sub(1) as f32;
+    let window_size = &app.window_size;
+    Some(match container.scrolling.align {
+        RowAlign::Left => (0., last),
+        RowAlign::Edge => (0., (last - (visible_tiles(app, c_idx, window_size) - 1.)).max(0.)),
+        RowAlign::Centered => {
+            let offset = focus_offset(app, c_idx, window_size);
+            (-offset, last - offset)
+        }
+    })
+}
+
+// Nearest scroll position that lines a tile up where the row's alignment rests
+pub fn snap_row_scroll(app: &App, c_idx: usize, scroll: f32) -> f32 {
+    match app.containers[c_idx].scrolling.align {
+        RowAlign::Centered => {
+            let offset = focus_offset(app, c_idx, &app.window_size);
+            (scroll + offset).round() - offset
+        }
+        RowAlign::Left | RowAlign::Edge => scroll.round(),
+    }
+}
+
+// Tile that should hold focus while a row is scrolled directly to the position, e.g. by dragging
+pub fn tile_for_scroll(app: &App, c_idx: usize, scroll: f32) -> usize {
+    let container = &app.containers[c_idx];
+    let window_size = &app.window_size;
+    let t = match container.scrolling.align {
+        RowAlign::Left => scroll.round(),
+        RowAlign::Centered => (scroll + focus_offset(app, c_idx, window_size)).round(),
+        // Keep the focused tile while it stays in view
+        RowAlign::Edge => {
+            let last_visible = scroll.floor() + visible_tiles(app, c_idx, window_size) - 1.;
+            container.desired_selected_tile_idx.max(scroll.ceil()).min(last_visible)
+        }
+    };
+
+    let len = container.images.len().max(1);
+    if container.scrolling.wrap {
+        (t as i64).rem_euclid(len as i64) as usize
+    } else {
+        util::clamp(t, 0., (len - 1) as f32) as usize
+    }
+}
+
+pub fn title_rect(app: &App, c_idx: usize, window_size: &(u32, u32)) -> Rect {
+    let origin = origin(window_size);
+    let container = &app.containers[c_idx];
+    Rect {
+        x: origin.0 + app.viewport.pos[0] + container.offset_x + (container.title.width as f32 / 2.) - app.tile_size.0 / 2.,
+        y: origin.1 + app.render_viewport_y() - row_offset(app, c_idx),
+        width: container.title.width as f32,
+        height: container.title.height as f32,
+    }
+}
+
+// Top edge of a row's tiles before per-tile offsets
+fn row_top(app: &App, c_idx: usize, viewport_y: f32, window_size: &(u32, u32)) -> f32 {
+    origin(window_size).1 + viewport_y - row_offset(app, c_idx) - app.title_height + app.row_height / 2.
+}
+
+// Unscaled slot a tile occupies for the given vertical and row scroll positions
+fn tile_slot(app: &App, c_idx: usize, t_idx: usize, viewport_y: f32, row_scroll: f32, window_size: &(u32, u32)) -> Rect {
+    let origin = origin(window_size);
+    let container = &app.containers[c_idx];
+    let size = tile_size(app, c_idx);
+    let (column, line) = tile_cell(app, c_idx, t_idx);
+    // Rows share a left edge whatever their tile size
+    let left = origin.0 + app.viewport.pos[0] + container.offset_x - app.tile_size.0 / 2.;
+    let scroll = if container.style.columns().is_some() { 0. } else { row_scroll };
+    let mut position = column as f32 - scroll;
+    // Of a wrapping row's repeats, use the one nearest the focused tile's place
+    if container.scrolling.wrap && !container.images.is_empty() {
+        let len = container.images.len() as f32;
+        let first = focus_offset(app, c_idx, window_size) - len / 2.;
+        position = (position - first).rem_euclid(len) + first;
+    }
+    let line_height = line_height(app, c_idx);
+    Rect {
+        x: left + size.0 / 2. + position * tile_spacing(app, c_idx),
+        y: row_top(app, c_idx, viewport_y, window_size) - line_height / 2. - line as f32 * (line_height + GRID_LINE_GAP),
+        width: size.0,
+        height: size.1,
+    }
+}
+
+// Where a slot sits this frame, whether or not a tile has arrived to fill it
+pub fn slot_rect(app: &App, c_idx: usize, t_idx: usize, window_size: &(u32, u32)) -> Rect {
+    tile_slot(
+        app,
+        c_idx,
+        t_idx,
+        app.render_viewport_y(),
+        app.render_row_scroll(c_idx),
+        window_size,
+    )
+}
+
+pub fn tile_rect(app: &App, c_idx: usize, t_idx: usize, window_size: &(u32, u32)) -> Rect {
+    let tile = &app.containers[c_idx].images[t_idx];
+    let slot = slot_rect(app, c_idx, t_idx, window_size);
+    Rect {
+        y: slot.y + tile.offset_y,
+        width: slot.width * tile.scale,
+        height: slot.height * tile.scale,
+        ..slot
+    }
+}
+
+// Every place a tile is drawn: wrapping rows repeat their tiles to fill the window
+pub fn tile_rects(app: &App, c_idx: usize, t_idx: usize, window_size: &(u32, u32)) -> Vec<Rect> {
+    let rect = tile_rect(app, c_idx, t_idx, window_size);
+    let container = &app.containers[c_idx];
+    if !container.scrolling.wrap {
+        return vec![rect];
+    }
+
+    let repeat_width = container.images.len() as f32 * tile_spacing(app, c_idx);
+    let repeats = (window_size.0 as f32 / repeat_width).ceil() as i32 + 1;
+    (-repeats..=repeats)
+        .map(|k| Rect {
+            x: rect.x + k as f32 * repeat_width,
+            ..rect
+        })
+        .filter(|e| e.x + e.width / 2. >= 0. && e.x - e.width / 2. <= window_size.0 as f32)
+        .collect()
+}
+
+// Where a tile will sit once all scrolling has come to rest, used for focus decisions
+pub fn settled_tile_rect(app: &App, c_idx: usize, t_idx: usize, window_size: &(u32, u32)) -> Rect {
+    settled_tile_rect_at(app, c_idx, t_idx, app.containers[c_idx].scroll_target, window_size)
+}
+
+// Same as `settled_tile_rect` with the row scrolled to `row_scroll`, for lining rows up by column
+pub fn settled_tile_rect_at(app: &App, c_idx: usize, t_idx: usize, row_scroll: f32, window_size: &(u32, u32)) -> Rect {
+    tile_slot(app, c_idx, t_idx, app.viewport.target_y, row_scroll, window_size)
+}
+
+// Row whose band of tiles covers the given layout-space height
+pub fn row_at(app: &App, y: f32, window_size: &(u32, u32)) -> Option<usize> {
+    let viewport_y = app.render_viewport_y();
+    (0..app.containers.len()).find(|c_idx| {
+        let top = row_top(app, *c_idx, viewport_y, window_size);
+        y <= top && y >= top - row_height(app, *c_idx)
+    })
+}
+
+pub fn tile_at(app: &App, x: f32, y: f32, window_size: &(u32, u32)) -> Option<(usize, usize)> {
+    let c_idx = row_at(app, y, window_size)?;
+    (0..app.containers[c_idx].images.len())
+        .find(|t_idx| tile_rects(app, c_idx, *t_idx, window_size).iter().any(|e| e.contains(x, y)))
+        .map(|t_idx| (c_idx, t_idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rects_partly_inside_the_window_are_on_screen() {
+        let window_size = (1920, 1080);
+        let rect = |x, y| Rect {
+            x,
+            y,
+            width: 500.,
+            height: 281.,
+        };
+        assert!(rect(960., 540.).is_on_screen(&window_size));
+        assert!(rect(-200., 540.).is_on_screen(&window_size));
+        assert!(rect(960., 1200.).is_on_screen(&window_size));
+        assert!(!rect(-300., 540.).is_on_screen(&window_size));
+        assert!(!rect(2200., 540.).is_on_screen(&window_size));
+        assert!(!rect(960., -200.).is_on_screen(&window_size));
+        assert!(!rect(960., 1300.).is_on_screen(&window_size));
+    }
+}
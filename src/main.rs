@@ -1,38 +1,143 @@
-use sfml::window::{Context, Event, Key, Style, Window};
-use std::collections::VecDeque;
+use sfml::window::{Event, Style, Window};
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
-use std::sync::{Arc, Mutex};
-use std::thread;
 
 extern crate nalgebra_glm as glm;
 extern crate sfml;
 
+mod animation;
 mod app_gl;
+mod artwork;
+mod clock;
+mod config;
+mod detail;
+mod error;
+mod focus;
+mod fuzzy;
+mod gamepad;
+mod home;
+mod input;
+mod item;
+mod layout;
+mod motion;
+mod mouse;
+mod music;
+mod net;
+mod overlay;
+mod pool;
+mod preview;
+mod repeat;
+mod row_style;
+mod screen;
+mod scroll;
+mod search;
+mod settings;
+mod skeleton;
+mod sound;
+mod spring;
+mod timeline;
+mod tween;
 mod util;
+mod video;
 
 #[derive(Debug)]
 pub struct Viewport {
     pos: [f32; 2],
+    // Vertical position the viewport is moving towards
+    target_y: f32,
 }
 
 impl Default for Viewport {
     fn default() -> Self {
-        Viewport { pos: [0., 0.] }
+        Viewport {
+            pos: [0., 0.],
+            target_y: 0.,
+        }
     }
 }
 
+// Progress reported by the loading runtime
 #[derive(Debug)]
-struct DImageLoaded {
-    texture_id: u32,
-    container_idx: usize,
+enum LoadEvent {
+    // Containers listed by the page, each becoming a row
+    PageLoaded {
+        containers: Vec<serde_json::Value>,
+    },
+    PageFailed {
+        error: String,
+    },
+    // Items of a row whose contents come from a separate set request
+    RowItems {
+        container_idx: usize,
+        items: Vec<item::Item>,
+    },
+    RowFailed {
+        container_idx: usize,
+    },
+    // Stand-in for a tile's artwork, decoded from the item data or the disk cache
+    PreviewLoaded {
+        container_idx: usize,
+        tile_idx: usize,
+        preview: preview::Preview,
+    },
+    TileLoaded {
+        container_idx: usize,
+        tile_idx: usize,
+        artwork: artwork::Artwork,
+    },
+    TileFailed {
+        container_idx: usize,
+        tile_idx: usize,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed,
 }
 
 #[derive(Debug)]
 pub struct DImage {
     pub scale: f32,
     pub border: f32,
-    pub texture_id: u32,
+    pub alpha: f32,
+    pub offset_y: f32,
+    // Only valid once the tile has loaded
+    texture_id: u32,
+    // Blurred stand-in shown until the artwork has faded in
+    pub preview_texture_id: Option<u32>,
+    pub state: LoadState,
+    pub item: item::Item,
+    pub overlay: overlay::TileOverlay,
+    // Animated artwork, decoded and played while the tile has focus
+    pub animation: Option<animation::Animation>,
+}
+
+impl DImage {
+    // Tile for an item whose artwork is still on its way
+    fn placeholder(app: &App, item: item::Item, style: row_style::RowStyle) -> Self {
+        DImage {
+            scale: 1.,
+            border: 0.,
+            alpha: 0.,
+            offset_y: 0.,
+            texture_id: 0,
+            preview_texture_id: None,
+            state: LoadState::Loading,
+            overlay: overlay::TileOverlay::new(&item, style, app.watch_progress.progress_for(&item)),
+            item,
+            animation: None,
+        }
+    }
+
+    pub fn texture(&self) -> Option<u32> {
+        match self.state {
+            LoadState::Loaded => Some(self.texture_id),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -41,18 +146,54 @@ pub struct DImageRow {
     pub images: Vec<DImage>,
     pub selected_tile_idx: f32,
     pub desired_selected_tile_idx: f32,
+    pub alpha: f32,
+    pub offset_x: f32,
+    pub has_entered: bool,
+    // Whether the row knows which items it holds; rows backed by a set request start out loading
+    pub load_state: LoadState,
+    pub style: row_style::RowStyle,
+    pub scrolling: row_style::RowScrolling,
+    // Row scroll position the row is moving towards
+    pub scroll_target: f32,
+    // Time since the row last moved, for rows that advance on their own
+    pub auto_advance_timer: f32,
+    // Lines of tiles the row takes up, eased when a grid learns how many items it holds
+    pub lines: f32,
 }
 
 impl Drop for DImageRow {
     fn drop(&mut self) {
         app_gl::release_texture(self.title.texture_id);
         for image in &self.images {
-            app_gl::release_texture(image.texture_id);
+            if let Some(texture_id) = image.texture() {
+                app_gl::release_texture(texture_id);
+            }
+            if let Some(texture_id) = image.preview_texture_id {
+                app_gl::release_texture(texture_id);
+            }
         }
     }
 }
 
-fn get_item_image_url_from_json_value(item: &serde_json::Value) -> String {
+impl DImageRow {
+    // Tile slots to lay out, counting skeletons shown until a loading row learns its items
+    pub fn slot_count(&self) -> usize {
+        match self.load_state {
+            LoadState::Loading => self.images.len().max(skeleton::ROW_SKELETON_TILES),
+            _ => self.images.len(),
+        }
+    }
+
+    // Lines the row settles at once its slots are laid out; scrolling rows keep to one
+    pub fn target_lines(&self) -> f32 {
+        match self.style.columns() {
+            Some(columns) => self.slot_count().div_ceil(columns).max(1) as f32,
+            None => 1.,
+        }
+    }
+}
+
+pub fn get_item_image_url_from_json_value(item: &serde_json::Value) -> String {
     let url;
     if item["image"]["tile"]["1.78"]["series"]["default"]["url"].is_string() {
         url = item["image"]["tile"]["1.78"]["series"]["default"]["url"].to_string();
@@ -61,7 +202,6 @@ fn get_item_image_url_from_json_value(item: &serde_json::Value) -> String {
     } else if item["image"]["tile"]["1.78"]["default"]["default"]["url"].is_string() {
         url = item["image"]["tile"]["1.78"]["default"]["default"]["url"].to_string();
     } else {
-        println!("Hello World! {}", 123);
         println!("Failed to fish out image url: {:?}", item["image"]["tile"]["1.78"]);
         return "".to_string();
     }
@@ -101,168 +241,364 @@ struct ImageLoadingBundle {
     pub refset_id: String,
     pub refset_type: String,
     pub container_idx: usize,
-    pub images_to_load: Vec<String>,
+    pub style: row_style::RowStyle,
+    // Shape of the row's tiles, for sizing BlurHash previews
+    pub aspect_ratio: f32,
+    pub items_to_load: Vec<item::Item>,
+}
+
+// Starts the loading runtime and fetches the page in the background, so the window is up and responsive
+// while it loads. Rows are added once it arrives as `LoadEvent::PageLoaded`.
+fn load_page_data(app: &mut App, tx: &Sender<LoadEvent>) -> Result<(), String> {
+    app.loader = net::Loader::start(&app.config)?;
+    let fetcher = app.loader.fetcher().ok_or_else(|| "Loading has shut down".to_string())?;
+    let page_url = app.config.page_url.clone();
+    let tx = tx.clone();
+    app.loader.spawn(async move {
+        let event = match load_page_containers(&fetcher, &page_url).await {
+            Ok(containers) => LoadEvent::PageLoaded { containers },
+            Err(error) => LoadEvent::PageFailed { error },
+        };
+        let _ = tx.send(event);
+    });
+    Ok(())
 }
 
-// Loads initial page data and kicks off worker threads to finish image loading and refset loading
-fn load_page_data(app: &mut App) -> Receiver<DImageLoaded> {
-    static APP_DATA_SOURCE: &str = "https://cd-static.bamgrid.com/dp-117731241344/home.json";
+async fn load_page_containers(fetcher: &net::Fetcher, page_url: &str) -> Result<Vec<serde_json::Value>, String> {
+    let resp = fetcher.get_text(page_url).await?;
+    let data: serde_json::Value = serde_json::from_str(&resp).map_err(|err| format!("Malformed page data from {}: {}", page_url, err))?;
+    let containers = data["data"]["StandardCollection"]["containers"]
+        .as_array()
+        .ok_or(format!("No containers in page data from {}", page_url))?;
+    Ok(containers.to_vec())
+}
 
-    let (tx, rx): (Sender<DImageLoaded>, Receiver<DImageLoaded>) = mpsc::channel();
-    let rows_to_load: Arc<Mutex<VecDeque<ImageLoadingBundle>>> = Arc::new(Mutex::new(VecDeque::new()));
-    let resp = reqwest::blocking::get(APP_DATA_SOURCE).unwrap().text().unwrap();
-    let data: serde_json::Value = serde_json::from_str(&resp).unwrap();
-    let json_containers: Vec<serde_json::Value> = data["data"]["StandardCollection"]["containers"].as_array().unwrap().to_vec();
-    let mut container_idx = 0;
+// Adds a row for each of the page's containers, then loads their sets and artwork in the background
+fn add_page_rows(app: &mut App, json_containers: Vec<serde_json::Value>, tx: &Sender<LoadEvent>) {
+    let fetcher = match app.loader.fetcher() {
+        Some(fetcher) => fetcher,
+        None => return,
+    };
+    let mut rows_to_load: Vec<ImageLoadingBundle> = Vec::new();
+    let mut container_idx = app.containers.len();
 
     for container in json_containers {
-        let title = app_gl::render_text_to_texture(&get_container_title_from_json_value(&container));
+        let title_text = get_container_title_from_json_value(&container);
+        let title = app_gl::render_text_to_texture(&title_text);
+        let style = app.row_styles.style_for(&container, &title_text);
+        let scrolling = app.row_styles.scrolling_for(&container, &title_text);
         let refset_id = get_container_refset_id_from_json_value(&container);
         let refset_type = get_container_refset_type_from_json_value(&container);
 
+        let (tile_width, tile_height) = layout::style_tile_size(app, style);
         let mut bundle = ImageLoadingBundle {
             refset_id,
             refset_type,
             container_idx,
-            images_to_load: Vec::new(),
+            style,
+            aspect_ratio: tile_width / tile_height,
+            items_to_load: Vec::new(),
         };
 
-        container_idx += 1;
-        app.containers.push(DImageRow {
-            images: Vec::new(),
-            title,
-            selected_tile_idx: 0.,
-            desired_selected_tile_idx: 0.,
-        });
         let items = container["set"]["items"].as_array();
         match items {
             Some(arr) => {
                 for item in arr.to_vec() {
-                    let url = get_item_image_url_from_json_value(&item);
-                    bundle.images_to_load.push(url);
+                    bundle.items_to_load.push(item::Item::from_json_value(&item));
                 }
             }
             _ => {}
         }
 
-        rows_to_load.lock().unwrap().push_back(bundle);
-    }
-
-    // Spawn threads to acquire images and populate refsets
-    for _thread_idx in 0..(num_cpus::get() - 1) {
-        spawn_worker_thread_for_thread_rows(&tx, &rows_to_load)
-    }
-
-    rx
-}
-
-fn spawn_worker_thread_for_thread_rows(tx: &Sender<DImageLoaded>, rows_to_load: &Arc<Mutex<VecDeque<ImageLoadingBundle>>>) {
-    static APP_DATA_REFSET_SOURCE: &str = "https://cd-static.bamgrid.com/dp-117731241344/sets/{{id}}.json";
-
-    let thread_tx = tx.clone();
-    let thread_rows_to_load = Arc::clone(&rows_to_load);
-
-    thread::spawn(move || {
-        loop {
-            // Create GL context to allow async threads to load image data
-            let _context = Context::new();
-            let client = reqwest::blocking::Client::new();
-            let row_to_load = thread_rows_to_load.lock().unwrap().pop_front();
-            match row_to_load {
-                Some(mut row_to_load) => {
-                    // Populate refset if needed
-                    if row_to_load.refset_id != "null" {
-                        let set_id = row_to_load.refset_id.to_string();
-                        let set_type = row_to_load.refset_type.to_string();
-                        let set_url = APP_DATA_REFSET_SOURCE.replace("{{id}}", &set_id);
-                        let ref_resp = reqwest::blocking::get(set_url).unwrap().text().unwrap();
-                        let ref_data: serde_json::Value = serde_json::from_str(&ref_resp).unwrap();
-
-                        let mut refset_data_key = set_type.to_string();
-                        for key in ref_data["data"].as_object().unwrap().keys() {
-                            refset_data_key = key.to_string();
-                            break;
-                        }
-
-                        let items = ref_data["data"][refset_data_key]["items"].as_array();
-                        match items {
-                            Some(arr) => {
-                                for item in arr.to_vec() {
-                                    let url = get_item_image_url_from_json_value(&item);
-                                    row_to_load.images_to_load.push(url);
-                                }
-                            }
-                            _ => {
-                                println!("Failed to load refset id: {:?}, type: {:?}", set_id, set_type);
-                            }
-                        }
-                    }
+        // Items listed in the page get placeholder tiles straight away so the page can be browsed while artwork loads
+        let images = bundle
+            .items_to_load
+            .iter()
+            .map(|item| DImage::placeholder(app, item.clone(), style))
+            .collect();
+        let load_state = if bundle.refset_id != "null" {
+            LoadState::Loading
+        } else {
+            LoadState::Loaded
+        };
 
-                    for image_url in &row_to_load.images_to_load {
-                        match app_gl::load_image_from_url(&client, &image_url) {
-                            Ok(texture_id) => {
-                                match thread_tx.send(DImageLoaded {
-                                    texture_id,
-                                    container_idx: row_to_load.container_idx,
-                                }) {
-                                    Err(_e) => {
-                                        return;
-                                    }
-                                    _ => {}
-                                };
-                            }
-                            _ => {}
-                        }
-                    }
+        container_idx += 1;
+        let mut row = DImageRow {
+            images,
+            title,
+            selected_tile_idx: 0.,
+            desired_selected_tile_idx: 0.,
+            alpha: 0.,
+            offset_x: 0.,
+            has_entered: false,
+            load_state,
+            style,
+            scrolling,
+            scroll_target: 0.,
+            auto_advance_timer: 0.,
+            lines: 1.,
+        };
+        row.lines = row.target_lines();
+        app.containers.push(row);
+
+        rows_to_load.push(bundle);
+    }
+
+    // Rows load side by side, with the fetcher capping how many requests are in flight across all of them
+    for row_to_load in rows_to_load {
+        let (tx, set_url, cancel) = (tx.clone(), app.config.set_url.clone(), app.loader.cancel_token());
+        app.loader.spawn(load_row(row_to_load, fetcher.clone(), tx, set_url, cancel));
+    }
+}
+
+// Items of a set that the page only references by id
+async fn load_refset_items(
+    fetcher: &net::Fetcher,
+    set_url_template: &str,
+    set_id: &str,
+    set_type: &str,
+) -> Result<Vec<item::Item>, String> {
+    let set_url = set_url_template.replace("{{id}}", set_id);
+    let ref_resp = fetcher.get_text(&set_url).await?;
+    let ref_data: serde_json::Value =
+        serde_json::from_str(&ref_resp).map_err(|err| format!("Malformed set data from {}: {}", set_url, err))?;
+
+    let mut refset_data_key = set_type.to_string();
+    if let Some(key) = ref_data["data"].as_object().and_then(|data| data.keys().next()) {
+        refset_data_key = key.to_string();
+    }
+
+    let items = ref_data["data"][refset_data_key]["items"]
+        .as_array()
+        .ok_or(format!("Failed to load refset id: {:?}, type: {:?}", set_id, set_type))?;
+    Ok(items.iter().map(item::Item::from_json_value).collect())
+}
+
+// Fills in a row's set items if it has any, then loads the artwork of all its tiles at once. Each
+// result is sent to the main thread as it arrives, already decoded and ready to upload.
+async fn load_row(
+    mut row_to_load: ImageLoadingBundle,
+    fetcher: net::Fetcher,
+    tx: Sender<LoadEvent>,
+    set_url: String,
+    cancel: pool::CancelToken,
+) {
+    let container_idx = row_to_load.container_idx;
+    // Populate refset if needed
+    if row_to_load.refset_id != "null" {
+        let event = match load_refset_items(&fetcher, &set_url, &row_to_load.refset_id, &row_to_load.refset_type).await {
+            Ok(items) => {
+                row_to_load.items_to_load.extend(items.iter().cloned());
+                LoadEvent::RowItems { container_idx, items }
+            }
+            Err(err) => {
+                println!("{}", err);
+                LoadEvent::RowFailed { container_idx }
+            }
+        };
+        if tx.send(event).is_err() {
+            return;
+        }
+    }
+
+    for (tile_idx, item) in row_to_load.items_to_load.iter().enumerate() {
+        let (fetcher, tx, cancel) = (fetcher.clone(), tx.clone(), cancel.clone());
+        let url = row_to_load.style.image_url(item);
+        let item_preview = row_to_load.style.image_preview(item);
+        let aspect_ratio = row_to_load.aspect_ratio;
+        tokio::spawn(async move {
+            if cancel.is_cancelled() {
+                return;
+            }
+            // The stand-in goes first, so it's in place before the artwork can arrive
+            let preview_url = url.clone();
+            let preview = tokio::task::spawn_blocking(move || preview::load(item_preview.as_deref(), &preview_url, aspect_ratio)).await;
+            if let Ok(Some(preview)) = preview {
+                if tx
+                    .send(LoadEvent::PreviewLoaded {
+                        container_idx,
+                        tile_idx,
+                        preview,
+                    })
+                    .is_err()
+                {
+                    return;
                 }
-                _ => {
-                    break;
+            }
+
+            let event = match fetcher.get_artwork(url, true).await {
+                Ok(artwork) => LoadEvent::TileLoaded {
+                    container_idx,
+                    tile_idx,
+                    artwork,
+                },
+                Err(err) => {
+                    println!("{}", err);
+                    LoadEvent::TileFailed { container_idx, tile_idx }
                 }
             };
-        }
-    });
+            // The app stops listening once it quits
+            let _ = tx.send(event);
+        });
+    }
+}
+
+// Animated values from the previous simulation step, used to interpolate rendering
+#[derive(Debug, Default)]
+pub struct MotionSnapshot {
+    viewport_y: f32,
+    row_scroll: Vec<f32>,
 }
 
-#[derive(Copy, Clone)]
-pub struct Animation {
-    pub position: f32,
-    pub current_value: f32,
-    pub desired_value: f32,
-    pub update_fn: fn(&mut App, f32, f32),
+impl MotionSnapshot {
+    fn capture(app: &App) -> Self {
+        MotionSnapshot {
+            viewport_y: app.viewport.pos[1],
+            row_scroll: app.containers.iter().map(|c| c.selected_tile_idx).collect(),
+        }
+    }
 }
 
 pub struct App {
     gl: app_gl::AppGL,
+    pub config: config::Config,
+    pub loader: net::Loader,
     background_image_texture_id: u32,
-    has_tiles_loaded: bool,
     title_height: f32,
     row_height: f32,
-    tile_width: f32,
+    tile_size: (f32, f32),
     pub selected_container_idx: usize,
-    pub animations: Vec<Animation>,
+    pub motion: motion::MotionConfig,
+    pub tweens: tween::Tweens,
+    pub springs: spring::Springs,
+    pub timelines: timeline::Timelines,
+    pub clock: clock::Clock,
+    pub bindings: input::Bindings,
+    pub row_styles: row_style::RowStyles,
+    pub scroll: scroll::ScrollSettings,
+    pub watch_progress: overlay::WatchProgress,
+    pub gamepad: gamepad::Gamepad,
+    pub mouse: mouse::Mouse,
+    pub key_repeat: repeat::KeyRepeat,
+    pub focus: focus::FocusManager,
+    pub window_size: (u32, u32),
+    pub previous_motion: MotionSnapshot,
+    pub render_alpha: f32,
     pub containers: Vec<DImageRow>,
     pub viewport: Viewport,
+    pub screens: screen::ScreenStack,
+    pub text_entry_suppressed: bool,
+    // Drives the shimmer of skeleton placeholders
+    pub skeleton_time: f32,
+    pub animations: animation::AnimationPlayer,
+    pub video_previews: video::VideoPreviews,
+    pub video: video::VideoPlayer,
+    pub sounds: sound::SoundManager,
+    pub music_settings: music::MusicSettings,
+    pub music: music::MusicPlayer,
 }
 
 impl Default for App {
     fn default() -> Self {
+        static FIXED_STEP: f32 = 1. / 120.;
+
         App {
             gl: app_gl::AppGL::default(),
-            background_image_texture_id: app_gl::load_image_from_disk("res/img/background.png", 1440, 1070).unwrap(),
-            has_tiles_loaded: false,
+            config: config::Config::default(),
+            loader: net::Loader::default(),
+            background_image_texture_id: app_gl::load_image_from_disk("res/img/background.png").unwrap(),
             title_height: 200.,
             row_height: 280.,
-            tile_width: 625.,
+            tile_size: (500., 281.),
             selected_container_idx: 0,
             containers: Vec::new(),
-            animations: Vec::new(),
+            motion: motion::MotionConfig::default(),
+            tweens: tween::Tweens::default(),
+            springs: spring::Springs::default(),
+            timelines: timeline::Timelines::default(),
+            clock: clock::Clock::new(Box::new(util::Timer::default()), FIXED_STEP),
+            bindings: input::Bindings::load_from_disk("res/bindings.json").unwrap_or_else(|err| {
+                println!("{}, using default bindings", err);
+                input::Bindings::default()
+            }),
+            row_styles: row_style::RowStyles::load_from_disk("res/row_styles.json").unwrap_or_else(|err| {
+                println!("{}, using default row styles", err);
+                row_style::RowStyles::default()
+            }),
+            scroll: scroll::ScrollSettings::load_from_disk("res/scroll.json").unwrap_or_else(|err| {
+                println!("{}, using default scroll settings", err);
+                scroll::ScrollSettings::default()
+            }),
+            watch_progress: overlay::WatchProgress::load_from_disk("res/watch_progress.json").unwrap_or_else(|err| {
+                println!("{}, starting without watch progress", err);
+                overlay::WatchProgress::default()
+            }),
+            gamepad: gamepad::Gamepad::default(),
+            mouse: mouse::Mouse::default(),
+            key_repeat: repeat::KeyRepeat::default(),
+            focus: focus::FocusManager::default(),
+            window_size: (0, 0),
+            previous_motion: MotionSnapshot::default(),
+            render_alpha: 1.,
             viewport: Viewport::default(),
+            screens: screen::ScreenStack::default(),
+            text_entry_suppressed: false,
+            skeleton_time: 0.,
+            animations: animation::AnimationPlayer::default(),
+            video_previews: video::VideoPreviews::load_from_disk("res/video_previews.json").unwrap_or_else(|err| {
+                println!("{}, starting without video previews", err);
+                video::VideoPreviews::default()
+            }),
+            video: video::VideoPlayer::default(),
+            sounds: {
+                let settings = sound::SoundSettings::load_from_disk("res/sounds.json").unwrap_or_else(|err| {
+                    println!("{}, using default sounds", err);
+                    sound::SoundSettings::default()
+                });
+                // Silent mode records sounds instead of playing them, for runs without an audio device
+                if std::env::var("SFML_EXAMPLE_SILENT").is_ok() {
+                    sound::SoundManager::recording(&settings)
+                } else {
+                    sound::SoundManager::new(Box::new(sound::SfmlSounds::load(&settings)), &settings)
+                }
+            },
+            music_settings: {
+                let mut settings = music::MusicSettings::load_from_disk(music::MUSIC_SETTINGS_PATH).unwrap_or_else(|err| {
+                    println!("{}, playing no music", err);
+                    music::MusicSettings::default()
+                });
+                // There are no user settings until the volume is first changed
+                if std::path::Path::new(music::USER_MUSIC_SETTINGS_PATH).exists() {
+                    if let Err(err) = settings.apply_user_settings(music::USER_MUSIC_SETTINGS_PATH) {
+                        println!("{}, using the default volume", err);
+                    }
+                }
+                settings
+            },
+            music: music::MusicPlayer::default(),
+        }
+    }
+}
+
+impl App {
+    pub fn render_viewport_y(&self) -> f32 {
+        let current = self.viewport.pos[1];
+        let previous = self.previous_motion.viewport_y;
+        previous + (current - previous) * self.render_alpha
+    }
+
+    pub fn render_row_scroll(&self, c_idx: usize) -> f32 {
+        let current = self.containers[c_idx].selected_tile_idx;
+        match self.previous_motion.row_scroll.get(c_idx) {
+            Some(previous) => previous + (current - previous) * self.render_alpha,
+            None => current,
         }
     }
 }
 
 impl Drop for App {
     fn drop(&mut self) {
+        self.screens = screen::ScreenStack::default();
         self.containers.clear();
         app_gl::release_texture(self.background_image_texture_id);
     }
@@ -275,164 +611,517 @@ fn handle_window_events(app: &mut App, window: &mut Window) {
                 window.close();
             }
             Event::KeyPressed { code, .. } => {
-                let containers = &mut app.containers;
-                match code {
-                    Key::Q => {
-                        window.close();
-                    }
-                    Key::D => {
-                        if containers[app.selected_container_idx].images.len() > 0
-                            && containers[app.selected_container_idx].desired_selected_tile_idx
-                                < (containers[app.selected_container_idx].images.len() - 1) as f32
-                        {
-                            containers[app.selected_container_idx].desired_selected_tile_idx += 1.;
-
-                            let animation = Animation {
-                                position: 0.,
-                                current_value: containers[app.selected_container_idx].selected_tile_idx,
-                                desired_value: containers[app.selected_container_idx].desired_selected_tile_idx,
-                                update_fn: |app, _i, new| {
-                                    app.containers[app.selected_container_idx].selected_tile_idx = new;
-                                },
-                            };
-                            app.animations.push(animation);
-                        }
-                    }
-                    Key::A => {
-                        if containers[app.selected_container_idx].desired_selected_tile_idx > 0. {
-                            containers[app.selected_container_idx].desired_selected_tile_idx -= 1.;
-
-                            let animation = Animation {
-                                position: 0.,
-                                current_value: containers[app.selected_container_idx].selected_tile_idx,
-                                desired_value: containers[app.selected_container_idx].desired_selected_tile_idx,
-                                update_fn: |app, _i, new| {
-                                    app.containers[app.selected_container_idx].selected_tile_idx = new;
-                                },
-                            };
-                            app.animations.push(animation);
-                        }
-                    }
-                    Key::W => {
-                        if app.selected_container_idx >= 1 {
-                            app.selected_container_idx -= 1;
-                            let animation = Animation {
-                                position: 0.,
-                                current_value: app.viewport.pos[1],
-                                desired_value: (app.title_height + app.row_height) * app.selected_container_idx as f32,
-                                update_fn: |app, _i, new| {
-                                    app.viewport.pos[1] = new;
-                                },
-                            };
-                            app.animations.push(animation);
-                        }
-                    }
-                    Key::S => {
-                        if app.selected_container_idx < app.containers.len() - 1 {
-                            app.selected_container_idx += 1;
-
-                            let animation = Animation {
-                                position: 0.,
-                                current_value: app.viewport.pos[1],
-                                desired_value: (app.title_height + app.row_height) * app.selected_container_idx as f32,
-                                update_fn: |app, _i, new| {
-                                    app.viewport.pos[1] = new;
-                                },
-                            };
-                            app.animations.push(animation);
-                        }
-                    }
-                    _ => {}
+                app.text_entry_suppressed = false;
+                if screen::captures_text(app) && input::is_text_key(code) {
+                    continue;
+                }
+                if let Some(action) = app.bindings.action_for(code) {
+                    // The text this key types belongs to the action, not to whatever screen it opens
+                    app.text_entry_suppressed = true;
+                    app.key_repeat.press(action);
+                    handle_action(app, window, action);
+                }
+            }
+            Event::TextEntered { unicode } => {
+                if !app.text_entry_suppressed {
+                    screen::handle_text(app, unicode);
+                }
+                app.text_entry_suppressed = false;
+            }
+            Event::KeyReleased { code, .. } => {
+                if let Some(action) = app.bindings.action_for(code) {
+                    app.key_repeat.release(action);
+                }
+            }
+            Event::LostFocus => {
+                app.key_repeat.release_all();
+            }
+            Event::MouseMoved { .. }
+            | Event::MouseButtonPressed { .. }
+            | Event::MouseButtonReleased { .. }
+            | Event::MouseWheelScrolled { .. } => {
+                let window_size = (window.size().x, window.size().y);
+                if let Some(action) = screen::map_mouse_event(app, &event, &window_size) {
+                    handle_action(app, window, action);
+                }
+            }
+            _ => {
+                if let Some(action) = app.gamepad.map_event(&event) {
+                    handle_action(app, window, action);
                 }
             }
-            _ => {}
         }
     }
 }
 
-fn process_tile_loads(app: &mut App, rx: &Receiver<DImageLoaded>) {
-    loop {
-        match rx.try_recv() {
-            Ok(image_loaded) => {
-                app.containers[image_loaded.container_idx].images.push(DImage {
-                    texture_id: image_loaded.texture_id,
-                    scale: 1.,
-                    border: 0.,
-                });
+fn handle_action(app: &mut App, window: &mut Window, action: input::Action) {
+    static SLOW_MOTION_SCALE: f32 = 0.25;
+
+    match action {
+        input::Action::Quit => {
+            window.close();
+        }
+        input::Action::Pause => {
+            app.clock.toggle_pause();
+        }
+        input::Action::SlowMotion => {
+            app.clock.toggle_slow_motion(SLOW_MOTION_SCALE);
+        }
+        input::Action::Step => {
+            app.clock.request_step();
+        }
+        input::Action::Mute => {
+            app.sounds.toggle_mute();
+        }
+        _ => {
+            screen::handle_action(app, action);
+        }
+    }
+}
+
+fn action_direction(action: input::Action) -> Option<focus::Direction> {
+    match action {
+        input::Action::Up => Some(focus::Direction::Up),
+        input::Action::Down => Some(focus::Direction::Down),
+        input::Action::Left => Some(focus::Direction::Left),
+        input::Action::Right => Some(focus::Direction::Right),
+        _ => None,
+    }
+}
+
+// Tile currently holding focus, falling back to the selected row's remembered tile
+fn focused_tile(app: &App) -> (usize, usize) {
+    match app.focus.current() {
+        Some(focus::FocusId::Tile(c_idx, t_idx)) => (c_idx, t_idx),
+        _ => (
+            app.selected_container_idx,
+            app.containers[app.selected_container_idx].desired_selected_tile_idx as usize,
+        ),
+    }
+}
+
+// Every tile where it will settle, or with all rows scrolled alike when `row_scroll` is given
+fn focusable_tiles(app: &App, row_scroll: Option<f32>) -> Vec<focus::Focusable> {
+    let mut focusables = Vec::new();
+    for (c_idx, container) in app.containers.iter().enumerate() {
+        for t_idx in 0..container.images.len() {
+            let rect = match row_scroll {
+                Some(row_scroll) => layout::settled_tile_rect_at(app, c_idx, t_idx, row_scroll, &app.window_size),
+                None => layout::settled_tile_rect(app, c_idx, t_idx, &app.window_size),
+            };
+            focusables.push(focus::Focusable {
+                id: focus::FocusId::Tile(c_idx, t_idx),
+                rect,
+            });
+        }
+    }
+    focusables
+}
+
+// Moves focus `count` steps in a direction using the on-screen position of every tile
+fn navigate(app: &mut App, direction: focus::Direction, count: i32) {
+    if app.containers.is_empty() {
+        return;
+    }
+
+    for _ in 0..count {
+        let (c_idx, t_idx) = focused_tile(app);
+        // Wrapping rows step round from one end to the other rather than searching on screen
+        let len = app.containers[c_idx].images.len();
+        if app.containers[c_idx].scrolling.wrap && len > 0 {
+            match direction {
+                focus::Direction::Left => {
+                    app.sounds.play_move(true);
+                    focus_tile(app, c_idx, (t_idx + len - 1) % len);
+                    continue;
+                }
+                focus::Direction::Right => {
+                    app.sounds.play_move(true);
+                    focus_tile(app, c_idx, (t_idx + 1) % len);
+                    continue;
+                }
+                _ => {}
             }
-            Err(_type) => {
+        }
+
+        let from = layout::settled_tile_rect(app, c_idx, t_idx, &app.window_size);
+        // Other rows are lined up with this one, so the nearest column means the same place in each row
+        // rather than wherever that row happens to be scrolled
+        let row_scroll = match app.focus.mode {
+            focus::FocusMode::NearestColumn => Some(app.containers[c_idx].scroll_target),
+            focus::FocusMode::RememberPerRow => None,
+        };
+        let next = match focus::find_next(&from, direction, &focusable_tiles(app, row_scroll)) {
+            Some(focus::FocusId::Tile(next_c_idx, next_t_idx)) => (next_c_idx, next_t_idx),
+            _ => {
+                app.sounds.play_move(false);
                 break;
             }
+        };
+
+        let (next_c_idx, mut next_t_idx) = next;
+        if next_c_idx != c_idx && app.focus.mode == focus::FocusMode::RememberPerRow {
+            let container = &app.containers[next_c_idx];
+            next_t_idx = util::clamp(container.desired_selected_tile_idx as usize, 0, container.images.len() - 1);
         }
+        app.sounds.play_move(true);
+        focus_tile(app, next_c_idx, next_t_idx);
     }
 }
 
-fn tick_animations(app: &mut App, dt: f32) {
-    static ANIMATION_MULT: f32 = 6.;
-    for animation in &mut app.animations {
-        animation.position = util::clamp(animation.position + dt * ANIMATION_MULT, 0., 1.);
+fn move_row_focus(app: &mut App, delta: i32) {
+    let c_idx = app.selected_container_idx;
+    let container = match app.containers.get(c_idx) {
+        Some(container) if !container.images.is_empty() => container,
+        _ => return,
+    };
+
+    let current = container.desired_selected_tile_idx as usize;
+    let t_idx = row_step(container.images.len(), current, delta, container.scrolling.wrap);
+    app.sounds.play_move(t_idx != current);
+    focus_tile(app, c_idx, t_idx);
+}
+
+// Tile `delta` places along a row of `len` tiles, going round wrapping rows and stopping at the ends of others
+fn row_step(len: usize, current: usize, delta: i32, wrap: bool) -> usize {
+    let len = len as i32;
+    let t_idx = current as i32 + delta;
+    if wrap {
+        t_idx.rem_euclid(len) as usize
+    } else {
+        util::clamp(t_idx, 0, len - 1) as usize
+    }
+}
+
+fn move_container_focus(app: &mut App, delta: i32) {
+    if delta < 0 {
+        navigate(app, focus::Direction::Up, -delta);
+    } else {
+        navigate(app, focus::Direction::Down, delta);
+    }
+}
+
+fn focus_tile(app: &mut App, c_idx: usize, t_idx: usize) {
+    if app.containers[c_idx].images.is_empty() {
+        return;
+    }
+
+    app.mouse.stop_inertia();
+    let desired_viewport_y = layout::focus_viewport_y(app, c_idx, t_idx);
+    app.selected_container_idx = c_idx;
+    if desired_viewport_y != app.viewport.target_y {
+        app.viewport.target_y = desired_viewport_y;
+        motion::animate_to(app, tween::Property::ViewportY, desired_viewport_y);
     }
 
-    // TODO: Fix this clunky mess
-    for i in 0..app.animations.len() {
-        let animation = app.animations[i];
-        let new_position = (1. - animation.position) * animation.current_value + (animation.position) * animation.desired_value;
-        (animation.update_fn)(app, animation.position, new_position);
+    let row_scroll = layout::row_scroll_target(app, c_idx, t_idx);
+    let container = &mut app.containers[c_idx];
+    container.auto_advance_timer = 0.;
+    container.desired_selected_tile_idx = t_idx as f32;
+    if container.scroll_target != row_scroll {
+        container.scroll_target = row_scroll;
+        motion::animate_to(app, tween::Property::RowScroll(c_idx), row_scroll);
     }
 
-    app.animations.retain(|e| e.position != 1.);
+    app.focus.set(focus::FocusId::Tile(c_idx, t_idx));
 }
 
-fn update(app: &mut App, dt: f32) {
-    static TILE_ZOOM_FACTOR: f32 = 1.20;
-    tick_animations(app, dt);
+// Zooms and outlines the newly focused tile and restores the previous one
+fn on_focus_changed(app: &mut App, event: focus::FocusChanged) {
+    static TILE_BORDER: f32 = 0.01;
 
-    if app.containers[1].images.len() > 3 {
-        app.has_tiles_loaded = true;
+    if let Some(focus::FocusId::Tile(c_idx, t_idx)) = event.from {
+        if let Some(tile) = app.containers[c_idx].images.get_mut(t_idx) {
+            tile.border = 0.;
+        }
+        motion::animate_to(app, tween::Property::TileScale(c_idx, t_idx), 1.);
     }
 
-    for (c_idx, container) in app.containers.iter_mut().enumerate() {
+    if let focus::FocusId::Tile(c_idx, t_idx) = event.to {
+        if let Some(tile) = app.containers[c_idx].images.get_mut(t_idx) {
+            tile.border = TILE_BORDER;
+        }
+        let zoom = app.containers[c_idx].style.focus_scale();
+        motion::animate_to(app, tween::Property::TileScale(c_idx, t_idx), zoom);
+    }
+}
 
-        let selected_tile_idx_i32 = container.desired_selected_tile_idx.round() as usize;
-        for (idx, tile) in container.images.iter_mut().enumerate() {
-            if c_idx == app.selected_container_idx && selected_tile_idx_i32 == idx {
-                tile.border = 0.01;
-                if tile.scale < TILE_ZOOM_FACTOR {
-                    tile.scale = util::clamp(tile.scale + dt, 1., TILE_ZOOM_FACTOR);
+// Applies what the loading runtime has sent, uploading a few tiles' artwork per frame so a burst of
+// arrivals spreads over several frames rather than stalling one; the rest stay queued for later frames
+fn process_tile_loads(app: &mut App, tx: &Sender<LoadEvent>, rx: &Receiver<LoadEvent>) {
+    static TILE_ENTRANCE_STAGGER: f32 = 0.04;
+    static TILE_ENTRANCE_DURATION: f32 = 0.35;
+    static TILE_ENTRANCE_OFFSET: f32 = -30.;
+    static TILE_UPLOADS_PER_FRAME: usize = 6;
+
+    let mut entrances = Vec::new();
+    while entrances.len() < TILE_UPLOADS_PER_FRAME {
+        match rx.try_recv() {
+            Ok(LoadEvent::PageLoaded { containers }) => add_page_rows(app, containers, tx),
+            Ok(LoadEvent::PageFailed { error }) => {
+                println!("{}", error);
+                screen::push(app, Box::new(error::ErrorScreen::new(&error)));
+            }
+            Ok(LoadEvent::RowItems { container_idx, items }) => {
+                let style = app.containers[container_idx].style;
+                let images: Vec<DImage> = items.into_iter().map(|item| DImage::placeholder(app, item, style)).collect();
+                let container = &mut app.containers[container_idx];
+                container.images.extend(images);
+                container.load_state = LoadState::Loaded;
+                // Rows below slide to make room rather than jumping
+                let lines = container.target_lines();
+                motion::animate_to(app, tween::Property::RowLines(container_idx), lines);
+            }
+            Ok(LoadEvent::RowFailed { container_idx }) => {
+                let container = &mut app.containers[container_idx];
+                container.load_state = LoadState::Failed;
+                let lines = container.target_lines();
+                motion::animate_to(app, tween::Property::RowLines(container_idx), lines);
+            }
+            Ok(LoadEvent::PreviewLoaded {
+                container_idx,
+                tile_idx,
+                preview,
+            }) => {
+                let image = &mut app.containers[container_idx].images[tile_idx];
+                if image.state == LoadState::Loading && image.preview_texture_id.is_none() {
+                    image.preview_texture_id = Some(preview.upload());
                 }
-            } else {
-                tile.border = 0.;
-                if tile.scale > 1. {
-                    tile.scale = util::clamp(tile.scale - dt, 1., TILE_ZOOM_FACTOR);
+            }
+            Ok(LoadEvent::TileLoaded {
+                container_idx,
+                tile_idx,
+                artwork,
+            }) => {
+                let image = &mut app.containers[container_idx].images[tile_idx];
+                image.texture_id = artwork.upload();
+                image.animation = artwork.animation;
+                image.state = LoadState::Loaded;
+                image.offset_y = TILE_ENTRANCE_OFFSET;
+
+                // Artwork fades in over the tile's skeleton
+                entrances.push(timeline::Timeline::Parallel(vec![
+                    timeline::Timeline::tween(
+                        tween::Property::TileAlpha(container_idx, tile_idx),
+                        0.,
+                        1.,
+                        TILE_ENTRANCE_DURATION,
+                        timeline::Ease::Linear,
+                    ),
+                    timeline::Timeline::tween(
+                        tween::Property::TileOffsetY(container_idx, tile_idx),
+                        TILE_ENTRANCE_OFFSET,
+                        0.,
+                        TILE_ENTRANCE_DURATION,
+                        timeline::Ease::OutCubic,
+                    ),
+                ]));
+            }
+            Ok(LoadEvent::TileFailed { container_idx, tile_idx }) => {
+                let image = &mut app.containers[container_idx].images[tile_idx];
+                image.state = LoadState::Failed;
+                // Nothing will fade in, so show the tile's overlay straight away
+                image.alpha = 1.;
+            }
+            Err(_type) => {
+                break;
+            }
+        }
+    }
+
+    if !entrances.is_empty() {
+        app.timelines.play(timeline::Timeline::Stagger {
+            interval: TILE_ENTRANCE_STAGGER,
+            children: entrances,
+        });
+    }
+}
+
+// Fades and slides in rows the first time they come within view
+fn play_row_entrances(app: &mut App) {
+    static ROW_ENTRANCE_STAGGER: f32 = 0.1;
+    static ROW_ENTRANCE_DURATION: f32 = 0.5;
+    static ROW_ENTRANCE_OFFSET: f32 = -80.;
+
+    let visible_height = app.window_size.1 as f32;
+    let row_tops: Vec<f32> = (0..app.containers.len())
+        .map(|c_idx| layout::row_offset(app, c_idx) - app.viewport.pos[1])
+        .collect();
+    let mut entrances = Vec::new();
+    for (c_idx, container) in app.containers.iter_mut().enumerate() {
+        let row_top = row_tops[c_idx];
+        if container.has_entered || row_top > visible_height {
+            continue;
+        }
+
+        container.has_entered = true;
+        container.offset_x = ROW_ENTRANCE_OFFSET;
+        entrances.push(timeline::Timeline::Parallel(vec![
+            timeline::Timeline::tween(
+                tween::Property::RowAlpha(c_idx),
+                0.,
+                1.,
+                ROW_ENTRANCE_DURATION,
+                timeline::Ease::Linear,
+            ),
+            timeline::Timeline::tween(
+                tween::Property::RowOffsetX(c_idx),
+                ROW_ENTRANCE_OFFSET,
+                0.,
+                ROW_ENTRANCE_DURATION,
+                timeline::Ease::OutCubic,
+            ),
+        ]));
+    }
+
+    if !entrances.is_empty() {
+        app.timelines.play(timeline::Timeline::Stagger {
+            interval: ROW_ENTRANCE_STAGGER,
+            children: entrances,
+        });
+    }
+}
+
+// Frees the stand-ins of tiles whose artwork has fully faded in over them
+fn release_covered_previews(app: &mut App) {
+    for container in &mut app.containers {
+        for image in &mut container.images {
+            if image.state == LoadState::Loaded && image.alpha >= 1. {
+                if let Some(texture_id) = image.preview_texture_id.take() {
+                    app_gl::release_texture(texture_id);
                 }
             }
         }
     }
 }
 
+fn update(app: &mut App, dt: f32) {
+    motion::tick(app, dt);
+    timeline::tick(app, dt);
+    release_covered_previews(app);
+    mouse::tick_inertia(app, dt);
+    screen::update(app, dt);
+    music::tick(app, dt);
+}
+
 fn main() {
-    static WINDOW_SIZE: (u32, u32) = (1920, 1080);
-    static WINDOW_FPS: u32 = 200;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match config::Config::load(&args) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    if config.print_config {
+        print!("{}", config.describe());
+        return;
+    }
+    let window_size = config.window_size();
 
     // Creates GL context internally
-    let mut window = Window::new(WINDOW_SIZE, "SFML Example", Style::CLOSE, &Default::default());
-    window.set_framerate_limit(WINDOW_FPS);
+    let mut window = Window::new(window_size, "SFML Example", Style::CLOSE, &Default::default());
+    window.set_framerate_limit(config.fps);
+    window.set_key_repeat_enabled(false);
 
     let mut app = App::default();
-    let loader_rx = load_page_data(&mut app);
-    let mut frame_timer = util::Timer::default();
+    app.window_size = window_size;
+    app.row_height = config.row_height;
+    app.title_height = config.title_height;
+    app.tile_size = config.tile_size();
+    app.config = config;
+    app.gamepad.scan_connected();
+    screen::push(&mut app, Box::new(home::Home));
+    preview::prune_cache();
+    let (loader_tx, loader_rx) = mpsc::channel();
+    if let Err(err) = load_page_data(&mut app, &loader_tx) {
+        println!("{}", err);
+        // Nothing will ever arrive; the home page stays empty behind the error
+        screen::push(&mut app, Box::new(error::ErrorScreen::new(&err)));
+    }
 
-    while window.is_open() {
-        let dt = frame_timer.dt();
+    // Frame-locked mode advances exactly one step per frame so runs replay identically
+    let frame_lock = match std::env::var("SFML_EXAMPLE_FRAME_LOCKED") {
+        Ok(_) => {
+            let manual_clock = clock::ManualClock::default();
+            app.clock = clock::Clock::new(Box::new(manual_clock.clone()), app.clock.step);
+            Some(manual_clock)
+        }
+        Err(_) => None,
+    };
 
+    while window.is_open() {
         handle_window_events(&mut app, &mut window);
-        process_tile_loads(&mut app, &loader_rx);
-        update(&mut app, dt);
+        process_tile_loads(&mut app, &loader_tx, &loader_rx);
+
+        if let Some(manual_clock) = &frame_lock {
+            manual_clock.advance(app.clock.step);
+        }
+
+        let steps = app.clock.advance();
+        let dt = app.clock.step;
+        if let Some((action, count)) = app.key_repeat.tick(app.clock.frame_time()) {
+            // Applied as one burst so fast repeats don't queue up separate retargets
+            screen::handle_repeated_action(&mut app, action, count);
+        }
+
+        for _ in 0..steps {
+            app.previous_motion = MotionSnapshot::capture(&app);
+            update(&mut app, dt);
+        }
+        app.render_alpha = app.clock.alpha();
 
         window.set_active(true);
 
-        app_gl::render(&app, &WINDOW_SIZE);
+        app_gl::render(&app, &window_size);
 
         window.display();
     }
+
+    // Quitting drops outstanding requests rather than leaving them to finish the page
+    app.loader.shutdown();
+
+    if let Some(played) = app.sounds.recorded() {
+        println!("Sounds played: {:?}", played);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sound::SoundEvent::{Bump, FocusMove};
+
+    // Moves along a row the way `move_row_focus` does, sounding each move
+    fn walk_row(sounds: &mut sound::SoundManager, len: usize, wrap: bool, deltas: &[i32]) -> usize {
+        deltas.iter().fold(0, |current, delta| {
+            let next = row_step(len, current, *delta, wrap);
+            sounds.play_move(next != current);
+            next
+        })
+    }
+
+    #[test]
+    fn row_moves_bump_at_the_ends() {
+        let mut sounds = sound::SoundManager::recording(&sound::SoundSettings::default());
+        assert_eq!(walk_row(&mut sounds, 3, false, &[-1, 1, 1, 1, 5]), 2);
+        assert_eq!(sounds.recorded().unwrap(), vec![Bump, FocusMove, FocusMove, Bump, Bump]);
+    }
+
+    #[test]
+    fn wrapping_rows_never_bump() {
+        let mut sounds = sound::SoundManager::recording(&sound::SoundSettings::default());
+        assert_eq!(walk_row(&mut sounds, 3, true, &[-1, 1, 1, 4]), 2);
+        assert_eq!(sounds.recorded().unwrap(), vec![FocusMove; 4]);
+    }
+
+    #[test]
+    fn muted_moves_are_silent() {
+        let mut sounds = sound::SoundManager::recording(&sound::SoundSettings::default());
+        sounds.toggle_mute();
+        walk_row(&mut sounds, 3, false, &[1, 1, 1]);
+        assert!(sounds.recorded().unwrap().is_empty());
+        sounds.toggle_mute();
+        walk_row(&mut sounds, 3, false, &[1]);
+        assert_eq!(sounds.recorded().unwrap(), vec![FocusMove]);
+    }
 }
@@ -0,0 +1,67 @@
+use crate::spring::{self, SpringParams};
+use crate::tween::{self, Property};
+use crate::App;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MotionStyle {
+    Tween,
+    Spring(SpringParams),
+}
+
+// Which integrator drives each kind of property
+#[derive(Debug, Copy, Clone)]
+pub struct MotionConfig {
+    pub viewport: MotionStyle,
+    pub row_scroll: MotionStyle,
+    pub tile_scale: MotionStyle,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        MotionConfig {
+            viewport: MotionStyle::Spring(SpringParams::default()),
+            row_scroll: MotionStyle::Spring(SpringParams::critically_damped(200.)),
+            tile_scale: MotionStyle::Tween,
+        }
+    }
+}
+
+impl MotionConfig {
+    pub fn style_for(&self, property: Property) -> MotionStyle {
+        match property {
+            Property::ViewportY => self.viewport,
+            Property::RowScroll(_) => self.row_scroll,
+            Property::TileScale(_, _) => self.tile_scale,
+            // Entrance properties are only driven by timelines
+            Property::RowAlpha(_) | Property::RowOffsetX(_) | Property::TileAlpha(_, _) | Property::TileOffsetY(_, _) => MotionStyle::Tween,
+            Property::RowLines(_) => MotionStyle::Tween,
+            // Screen transitions need a completion callback, which only tweens provide
+            Property::ScreenProgress(_) => MotionStyle::Tween,
+        }
+    }
+}
+
+// Moves the property towards `to` with whichever motion style is configured for it
+pub fn animate_to(app: &mut App, property: Property, to: f32) {
+    match app.motion.style_for(property) {
+        MotionStyle::Tween => {
+            app.springs.cancel(property);
+            tween::animate_to(app, property, to);
+        }
+        MotionStyle::Spring(params) => {
+            app.tweens.cancel(property);
+            spring::spring_to(app, property, to, params);
+        }
+    }
+}
+
+// Stops any motion on the property so it can be set directly
+pub fn cancel(app: &mut App, property: Property) {
+    app.tweens.cancel(property);
+    app.springs.cancel(property);
+}
+
+pub fn tick(app: &mut App, dt: f32) {
+    tween::tick(app, dt);
+    spring::tick(app, dt);
+}
@@ -0,0 +1,214 @@
+use sfml::window::mouse::{Button, Wheel};
+use sfml::window::{Event, Key};
+
+use crate::focus::FocusId;
+use crate::input::Action;
+use crate::tween::{self, Property};
+use crate::{layout, motion, util, App};
+
+// Distance in pixels a press has to travel before it counts as a drag rather than a click
+static DRAG_THRESHOLD: f32 = 10.;
+// Exponential decay rate of fling velocity per second
+static INERTIA_FRICTION: f32 = 4.;
+// Fling speed in tiles per second below which the row snaps to the nearest tile
+static INERTIA_REST_VELOCITY: f32 = 0.5;
+
+#[derive(Debug)]
+struct Drag {
+    c_idx: usize,
+    start_x: f32,
+    start_scroll: f32,
+    last_x: f32,
+    // Simulation time of the last velocity sample
+    last_time: f64,
+    velocity: f32,
+    is_dragging: bool,
+}
+
+#[derive(Debug)]
+struct Inertia {
+    c_idx: usize,
+    velocity: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct Mouse {
+    drag: Option<Drag>,
+    inertia: Option<Inertia>,
+    wheel_accumulator: (f32, f32),
+}
+
+impl Mouse {
+    pub fn stop_inertia(&mut self) {
+        self.inertia = None;
+    }
+}
+
+// Moves the row's scroll position directly, keeping focus on whichever tile the position favours
+fn scroll_row_to(app: &mut App, c_idx: usize, scroll: f32) -> f32 {
+    let scroll = match layout::row_scroll_range(app, c_idx) {
+        Some((lowest, highest)) => util::clamp(scroll, lowest, highest),
+        None => scroll,
+    };
+    motion::cancel(app, Property::RowScroll(c_idx));
+    tween::set_property(app, Property::RowScroll(c_idx), scroll);
+    let t_idx = layout::tile_for_scroll(app, c_idx, scroll);
+    let container = &mut app.containers[c_idx];
+    container.scroll_target = scroll;
+    container.desired_selected_tile_idx = t_idx as f32;
+    if !container.images.is_empty() {
+        app.focus.set(FocusId::Tile(c_idx, t_idx));
+    }
+    scroll
+}
+
+// Focuses the tile under the pointer without scrolling to it. Scrolling would slide other tiles under a
+// pointer that hasn't moved, and the next move would focus and scroll to one of those.
+fn hover_tile(app: &mut App, c_idx: usize, t_idx: usize) {
+    app.selected_container_idx = c_idx;
+    app.containers[c_idx].desired_selected_tile_idx = t_idx as f32;
+    app.focus.set(FocusId::Tile(c_idx, t_idx));
+}
+
+// Whole wheel notches accumulated so far, keeping the fractional remainder for high precision wheels
+fn take_notches(accumulator: &mut f32, delta: f32) -> i32 {
+    *accumulator += delta;
+    let notches = accumulator.trunc();
+    *accumulator -= notches;
+    notches as i32
+}
+
+pub fn map_event(app: &mut App, event: &Event, window_size: &(u32, u32)) -> Option<Action> {
+    match *event {
+        Event::MouseMoved { x, y } => {
+            let (x, y) = layout::from_window_coords(x, y, window_size);
+            if app.mouse.drag.is_some() {
+                update_drag(app, x);
+            } else if let Some((c_idx, t_idx)) = layout::tile_at(app, x, y, window_size) {
+                hover_tile(app, c_idx, t_idx);
+            }
+            None
+        }
+        Event::MouseButtonPressed {
+            button: Button::LEFT,
+            x,
+            y,
+        } => {
+            let (x, y) = layout::from_window_coords(x, y, window_size);
+            app.mouse.inertia = None;
+            // Wrapped rows show every tile already, so there is nothing to drag
+            let c_idx = layout::row_at(app, y, window_size).filter(|c_idx| app.containers[*c_idx].style.columns().is_none());
+            if let Some(c_idx) = c_idx {
+                app.mouse.drag = Some(Drag {
+                    c_idx,
+                    start_x: x,
+                    start_scroll: app.containers[c_idx].selected_tile_idx,
+                    last_x: x,
+                    last_time: app.clock.time(),
+                    velocity: 0.,
+                    is_dragging: false,
+                });
+            }
+            None
+        }
+        Event::MouseButtonReleased {
+            button: Button::LEFT,
+            x,
+            y,
+        } => {
+            let (x, y) = layout::from_window_coords(x, y, window_size);
+            match app.mouse.drag.take() {
+                Some(drag) if drag.is_dragging => {
+                    app.mouse.inertia = Some(Inertia {
+                        c_idx: drag.c_idx,
+                        velocity: drag.velocity,
+                    });
+                    None
+                }
+                _ => {
+                    let (c_idx, t_idx) = layout::tile_at(app, x, y, window_size)?;
+                    crate::focus_tile(app, c_idx, t_idx);
+                    Some(Action::Select)
+                }
+            }
+        }
+        Event::MouseWheelScrolled { wheel, delta, .. } => {
+            let shift = Key::LSHIFT.is_pressed() || Key::RSHIFT.is_pressed();
+            // Positive deltas scroll up or left
+            if wheel == Wheel::Horizontal || shift {
+                let notches = take_notches(&mut app.mouse.wheel_accumulator.0, delta);
+                if notches != 0 {
+                    crate::move_row_focus(app, -notches);
+                }
+            } else {
+                let notches = take_notches(&mut app.mouse.wheel_accumulator.1, delta);
+                if notches != 0 {
+                    crate::move_container_focus(app, -notches);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+fn update_drag(app: &mut App, x: f32) {
+    let tile_width = match &app.mouse.drag {
+        Some(drag) => layout::tile_spacing(app, drag.c_idx),
+        None => return,
+    };
+    let now = app.clock.time();
+    let drag = match app.mouse.drag.as_mut() {
+        Some(drag) => drag,
+        None => return,
+    };
+
+    // Moves within one frame share a timestamp, so they're measured together on the next frame
+    let dt = (now - drag.last_time) as f32;
+    if dt > 0. {
+        let velocity = -(x - drag.last_x) / tile_width / dt;
+        drag.velocity = 0.8 * velocity + 0.2 * drag.velocity;
+        drag.last_x = x;
+        drag.last_time = now;
+    }
+
+    if !drag.is_dragging && (x - drag.start_x).abs() < DRAG_THRESHOLD {
+        return;
+    }
+
+    let became_drag = !drag.is_dragging;
+    drag.is_dragging = true;
+    let c_idx = drag.c_idx;
+    let scroll = drag.start_scroll - (x - drag.start_x) / tile_width;
+
+    if became_drag && c_idx != app.selected_container_idx {
+        let t_idx = app.containers[c_idx].desired_selected_tile_idx as usize;
+        crate::focus_tile(app, c_idx, t_idx);
+    }
+    scroll_row_to(app, c_idx, scroll);
+}
+
+// Carries a released drag forward, then settles the row on the nearest tile
+pub fn tick_inertia(app: &mut App, dt: f32) {
+    let (c_idx, velocity) = match &app.mouse.inertia {
+        Some(inertia) => (inertia.c_idx, inertia.velocity),
+        None => return,
+    };
+
+    let current = app.containers[c_idx].selected_tile_idx;
+    let scroll = scroll_row_to(app, c_idx, current + velocity * dt);
+    let velocity = velocity * (-INERTIA_FRICTION * dt).exp();
+    let hit_edge = match layout::row_scroll_range(app, c_idx) {
+        Some((lowest, highest)) => (scroll == lowest && velocity < 0.) || (scroll == highest && velocity > 0.),
+        None => false,
+    };
+
+    if velocity.abs() < INERTIA_REST_VELOCITY || hit_edge {
+        app.mouse.inertia = None;
+        let rest = layout::snap_row_scroll(app, c_idx, scroll);
+        app.containers[c_idx].scroll_target = rest;
+        motion::animate_to(app, Property::RowScroll(c_idx), rest);
+    } else if let Some(inertia) = app.mouse.inertia.as_mut() {
+        inertia.velocity = velocity;
+    }
+}
@@ -0,0 +1,221 @@
+use sfml::audio::{Music, SoundSource};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::focus::FocusId;
+use crate::screen::ScreenKind;
+use crate::{util, App};
+
+pub static MUSIC_SETTINGS_PATH: &str = "res/music.json";
+// Volume chosen in the settings screen, kept out of the checked-in resources
+pub static USER_MUSIC_SETTINGS_PATH: &str = "cache/music.json";
+static CROSSFADE_DURATION: f32 = 1.5;
+// Music level while a UI sound or video preview plays over it, and how fast it dips and recovers
+static DUCK_LEVEL: f32 = 0.35;
+static DUCK_SPEED: f32 = 4.;
+static DEFAULT_VOLUME: f32 = 0.6;
+
+static SCREEN_NAMES: [(&str, ScreenKind); 5] = [
+    ("Home", ScreenKind::Home),
+    ("Detail", ScreenKind::Detail),
+    ("Search", ScreenKind::Search),
+    ("Settings", ScreenKind::Settings),
+    ("Error", ScreenKind::Error),
+];
+
+// Which local tracks play where, and how loud
+#[derive(Debug)]
+pub struct MusicSettings {
+    pub volume: f32,
+    // Ambient track for each screen
+    screens: Vec<(ScreenKind, String)>,
+    // Theme played while an item is focused or open, by item id
+    themes: Vec<(String, String)>,
+}
+
+impl Default for MusicSettings {
+    fn default() -> Self {
+        MusicSettings {
+            volume: DEFAULT_VOLUME,
+            screens: Vec::new(),
+            themes: Vec::new(),
+        }
+    }
+}
+
+impl MusicSettings {
+    // Loads settings of the form { "volume": 0.6, "screens": { "Home": "res/music/home.ogg" }, "themes": { "<content id>": "res/music/x.ogg" } }
+    pub fn load_from_disk(path: &str) -> Result<MusicSettings, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open music settings file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read music settings file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed music settings file {}: {:?}", path, err))?;
+        if !data.is_object() {
+            return Err(format!("Music settings file {} should be an object", path));
+        }
+
+        let mut settings = MusicSettings::default();
+        if let Some(volume) = data["volume"].as_f64() {
+            settings.volume = util::clamp(volume as f32, 0., 1.);
+        }
+        if let Some(screens) = data["screens"].as_object() {
+            for (screen_name, track) in screens {
+                let kind = SCREEN_NAMES
+                    .iter()
+                    .find(|(n, _)| n == screen_name)
+                    .map(|(_, k)| *k)
+                    .ok_or(format!("Unknown screen in music settings: {:?}", screen_name))?;
+                let track = track.as_str().ok_or(format!("Music track for {} should be a path", screen_name))?;
+                settings.screens.push((kind, track.to_string()));
+            }
+        }
+        if let Some(themes) = data["themes"].as_object() {
+            for (id, track) in themes {
+                let track = track.as_str().ok_or(format!("Theme for {} should be a path", id))?;
+                settings.themes.push((id.to_string(), track.to_string()));
+            }
+        }
+
+        Ok(settings)
+    }
+
+    // Applies the user's own choices, of the form { "volume": 0.4 }, over settings from the resources
+    pub fn apply_user_settings(&mut self, path: &str) -> Result<(), String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open user music settings file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read user music settings file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed user music settings file {}: {:?}", path, err))?;
+        let volume = data["volume"]
+            .as_f64()
+            .ok_or(format!("User music settings file {} should hold a volume", path))?;
+        self.volume = util::clamp(volume as f32, 0., 1.);
+        Ok(())
+    }
+
+    pub fn save_user_settings(&self, path: &str) -> Result<(), String> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).map_err(|err| format!("Failed to create directory for {}: {:?}", path, err))?;
+        }
+        let contents = serde_json::json!({ "volume": self.volume }).to_string();
+        let mut f = File::create(path).map_err(|err| format!("Failed to create user music settings file {}: {:?}", path, err))?;
+        f.write_all(contents.as_bytes())
+            .map_err(|err| format!("Failed to write user music settings file {}: {:?}", path, err))
+    }
+
+    fn screen_track(&self, kind: ScreenKind) -> Option<&str> {
+        self.screens.iter().find(|(k, _)| *k == kind).map(|(_, track)| track.as_str())
+    }
+
+    fn theme(&self, id: &str) -> Option<&str> {
+        self.themes.iter().find(|(i, _)| i == id).map(|(_, track)| track.as_str())
+    }
+}
+
+struct Track {
+    path: String,
+    // None when the file couldn't be opened, so it isn't retried every update
+    music: Option<Music>,
+    // Crossfade level from 0 to 1
+    gain: f32,
+    leaving: bool,
+}
+
+// Streams the music for the current screen or focused item, crossfading whenever it changes
+#[derive(Default)]
+pub struct MusicPlayer {
+    tracks: Vec<Track>,
+    duck: f32,
+}
+
+fn open_track(path: &str) -> Track {
+    let music = Music::from_file(path).map(|mut music| {
+        music.set_looping(true);
+        music.set_volume(0.);
+        music.play();
+        music
+    });
+    if music.is_none() {
+        println!("Failed to open music track {}", path);
+    }
+    Track {
+        path: path.to_string(),
+        music,
+        gain: 0.,
+        leaving: false,
+    }
+}
+
+// Item theme while an item is focused or open, otherwise the active screen's ambient track
+fn wanted_track(app: &App) -> Option<String> {
+    let kind = app.screens.active_kind()?;
+    if kind == ScreenKind::Home || kind == ScreenKind::Detail {
+        if let Some(FocusId::Tile(c_idx, t_idx)) = app.focus.current() {
+            let theme = app
+                .containers
+                .get(c_idx)
+                .and_then(|container| container.images.get(t_idx))
+                .and_then(|image| image.item.id.as_ref())
+                .and_then(|id| app.music_settings.theme(id));
+            if let Some(theme) = theme {
+                return Some(theme.to_string());
+            }
+        }
+    }
+    app.music_settings.screen_track(kind).map(|track| track.to_string())
+}
+
+pub fn tick(app: &mut App, dt: f32) {
+    let wanted = wanted_track(app);
+    let ducked = app.sounds.is_playing() || app.video.is_playing();
+    let volume = app.music_settings.volume;
+    let player = &mut app.music;
+
+    for track in &mut player.tracks {
+        track.leaving = Some(&track.path) != wanted.as_ref();
+    }
+    if let Some(wanted) = wanted {
+        if !player.tracks.iter().any(|track| track.path == wanted) {
+            player.tracks.push(open_track(&wanted));
+        }
+    }
+
+    let duck_target = if ducked { 1. } else { 0. };
+    player.duck += util::clamp(duck_target - player.duck, -DUCK_SPEED * dt, DUCK_SPEED * dt);
+    let duck = 1. - (1. - DUCK_LEVEL) * player.duck;
+
+    for track in &mut player.tracks {
+        let step = dt / CROSSFADE_DURATION;
+        track.gain = util::clamp(track.gain + if track.leaving { -step } else { step }, 0., 1.);
+        if let Some(music) = &mut track.music {
+            music.set_volume(volume * track.gain * duck * 100.);
+        }
+    }
+    player.tracks.retain(|track| !(track.leaving && track.gain <= 0.));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_volume_overlays_the_resources() {
+        let dir = std::env::temp_dir().join(format!("sfml_example_music_{}", std::process::id()));
+        let path = dir.join("music.json");
+        let path = path.to_str().unwrap();
+
+        let mut settings = MusicSettings::load_from_disk(MUSIC_SETTINGS_PATH).unwrap();
+        settings.volume = 0.25;
+        settings.save_user_settings(path).unwrap();
+
+        let mut reloaded = MusicSettings::load_from_disk(MUSIC_SETTINGS_PATH).unwrap();
+        assert_eq!(reloaded.volume, DEFAULT_VOLUME);
+        reloaded.apply_user_settings(path).unwrap();
+        assert_eq!(reloaded.volume, 0.25);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use reqwest::StatusCode;
+use std::future::Future;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::artwork::{self, Artwork};
+use crate::config::Config;
+use crate::pool::{CancelToken, WorkerPool};
+
+// First wait before retrying a failed request; each further retry waits twice as long
+static RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+// Wait before the given retry, saturating rather than overflowing however many retries are allowed
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt))
+}
+
+// HTTP client shared by every loading task. Connections are pooled per host and multiplexed over
+// HTTP/2 where the server offers it, with the number of requests in flight capped.
+#[derive(Clone)]
+pub struct Fetcher {
+    client: reqwest::Client,
+    requests: Arc<Semaphore>,
+    retries: u32,
+}
+
+impl Fetcher {
+    fn new(config: &Config) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .connect_timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|err| format!("Failed to create http client: {}", err))?;
+        Ok(Fetcher {
+            client,
+            requests: Arc::new(Semaphore::new(config.max_requests)),
+            retries: config.retries,
+        })
+    }
+
+    // One attempt, with whether a failure is worth retrying
+    async fn try_get(&self, url: &str) -> Result<Vec<u8>, (String, bool)> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|err| (format!("Failed to load {}: {}", url, err), err.is_timeout() || err.is_connect()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let retryable = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+            return Err((format!("Failed to load {}: {}", url, status), retryable));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|err| (format!("Failed to read {}: {}", url, err), true))?;
+        Ok(bytes.to_vec())
+    }
+
+    // Retries timeouts, dropped connections and server errors, backing off exponentially between attempts
+    pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.requests.acquire().await.map_err(|_| "Loading has shut down".to_string())?;
+                self.try_get(url).await
+            };
+            match result {
+                Ok(bytes) => return Ok(bytes),
+                Err((err, true)) if attempt < self.retries => {
+                    println!("{}, retrying", err);
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+                Err((err, _)) => return Err(err),
+            }
+        }
+    }
+
+    pub async fn get_text(&self, url: &str) -> Result<String, String> {
+        let bytes = self.get_bytes(url).await?;
+        String::from_utf8(bytes).map_err(|err| format!("Response from {} isn't text: {}", url, err))
+    }
+
+    // Downloads and decodes artwork, decoding on a blocking thread so downloads keep moving
+    pub async fn get_artwork(&self, url: String, cache_preview: bool) -> Result<Artwork, String> {
+        let bytes = self.get_bytes(&url).await?;
+        tokio::task::spawn_blocking(move || artwork::decode(&url, &bytes, cache_preview))
+            .await
+            .map_err(|err| format!("Artwork decoding stopped: {}", err))?
+    }
+}
+
+// Worker pool doing all content and artwork loading, whose results come back to the main thread over channels
+#[derive(Default)]
+pub struct Loader {
+    pool: WorkerPool,
+    fetcher: Option<Fetcher>,
+}
+
+impl Loader {
+    pub fn start(config: &Config) -> Result<Loader, String> {
+        Ok(Loader {
+            pool: WorkerPool::start("loader", config.workers)?,
+            fetcher: Some(Fetcher::new(config)?),
+        })
+    }
+
+    pub fn fetcher(&self) -> Option<Fetcher> {
+        self.fetcher.clone()
+    }
+
+    pub fn cancel_token(&self) -> CancelToken {
+        self.pool.cancel_token()
+    }
+
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.pool.spawn(task);
+    }
+
+    // Artwork delivered over the returned channel once loaded; nothing arrives if it fails
+    pub fn load_artwork(&self, url: String) -> Receiver<Artwork> {
+        let (tx, rx) = mpsc::channel();
+        if let Some(fetcher) = self.fetcher() {
+            self.spawn(async move {
+                match fetcher.get_artwork(url, false).await {
+                    Ok(artwork) => {
+                        // The receiver is gone if the page was closed before the artwork arrived
+                        let _ = tx.send(artwork);
+                    }
+                    Err(err) => println!("{}", err),
+                }
+            });
+        }
+        rx
+    }
+
+    // Cancels outstanding loads without waiting out requests under way
+    pub fn shutdown(&mut self) {
+        self.pool.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_and_saturates() {
+        assert_eq!(retry_delay(0), RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(3), RETRY_BASE_DELAY * 8);
+        assert_eq!(retry_delay(40), RETRY_BASE_DELAY.saturating_mul(u32::MAX));
+    }
+}
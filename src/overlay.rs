@@ -0,0 +1,218 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::item::Item;
+use crate::layout::Rect;
+use crate::row_style::RowStyle;
+use crate::{app_gl, util, App};
+
+static CAPTION_CHAR_SIZE: isize = 30 * 32;
+static BADGE_CHAR_SIZE: isize = 22 * 32;
+// Captions are cut rather than wrapped so they stay on one line under the tile
+static CAPTION_MAX_CHARS: usize = 32;
+static CAPTION_GAP: f32 = 36.;
+static BADGE_PADDING: (f32, f32) = (14., 8.);
+static BADGE_MARGIN: f32 = 12.;
+static PROGRESS_BAR_HEIGHT: f32 = 8.;
+static PROGRESS_BAR_MARGIN: f32 = 16.;
+// Items released this many years ago or later are marked as new
+static NEW_RELEASE_YEARS: i64 = 1;
+
+static NEW_BADGE_COLOR: [f32; 4] = [0.2, 0.45, 0.9, 1.];
+static RATING_BADGE_COLOR: [f32; 4] = [0., 0., 0., 0.7];
+static PROGRESS_TRACK_COLOR: [f32; 4] = [1., 1., 1., 0.3];
+static PROGRESS_FILL_COLOR: [f32; 4] = [0.2, 0.45, 0.9, 1.];
+
+// Continue watching positions kept on this machine, as a fraction of the runtime per item id
+#[derive(Debug, Default)]
+pub struct WatchProgress {
+    progress: HashMap<String, f32>,
+}
+
+impl WatchProgress {
+    // Loads positions of the form { "<content id>": 0.35, ... }
+    pub fn load_from_disk(path: &str) -> Result<WatchProgress, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open watch progress file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read watch progress file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed watch progress file {}: {:?}", path, err))?;
+        let entries = data
+            .as_object()
+            .ok_or(format!("Watch progress file {} should be an object", path))?;
+
+        let mut progress = HashMap::new();
+        for (id, fraction) in entries {
+            let fraction = fraction.as_f64().ok_or(format!("Watch progress for {} should be a number", id))?;
+            progress.insert(id.to_string(), util::clamp(fraction as f32, 0., 1.));
+        }
+
+        Ok(WatchProgress { progress })
+    }
+
+    pub fn progress_for(&self, item: &Item) -> Option<f32> {
+        self.progress.get(item.id.as_ref()?).copied()
+    }
+}
+
+#[derive(Debug)]
+struct Badge {
+    text: app_gl::RenderedImage,
+    color: [f32; 4],
+}
+
+// Text and markers drawn over a tile's artwork
+#[derive(Debug)]
+pub struct TileOverlay {
+    // Shown below the tile while it has focus
+    caption: Option<String>,
+    badges: Vec<(String, [f32; 4])>,
+    progress: Option<f32>,
+    // Text is only rendered once the tile is first drawn visibly, so tiles never scrolled into view cost nothing
+    rendered: OnceCell<RenderedOverlay>,
+}
+
+#[derive(Debug)]
+struct RenderedOverlay {
+    caption: Option<app_gl::RenderedImage>,
+    badges: Vec<Badge>,
+}
+
+impl Drop for TileOverlay {
+    fn drop(&mut self) {
+        if let Some(rendered) = self.rendered.get() {
+            if let Some(caption) = &rendered.caption {
+                app_gl::release_texture(caption.texture_id);
+            }
+            for badge in &rendered.badges {
+                app_gl::release_texture(badge.text.texture_id);
+            }
+        }
+    }
+}
+
+fn current_year() -> i64 {
+    static SECONDS_PER_YEAR: u64 = 31_556_952;
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    1970 + (since_epoch / SECONDS_PER_YEAR) as i64
+}
+
+fn badges_for(item: &Item) -> Vec<(String, [f32; 4])> {
+    let mut badges = Vec::new();
+    if matches!(item.release_year, Some(year) if year >= current_year() - NEW_RELEASE_YEARS) {
+        badges.push(("NEW".to_string(), NEW_BADGE_COLOR));
+    }
+    if let Some(rating) = &item.rating {
+        badges.push((rating.to_string(), RATING_BADGE_COLOR));
+    }
+    badges
+}
+
+impl TileOverlay {
+    pub fn new(item: &Item, style: RowStyle, progress: Option<f32>) -> Self {
+        let caption = if style.shows_captions() && !item.title.is_empty() {
+            Some(item.title.chars().take(CAPTION_MAX_CHARS).collect())
+        } else {
+            None
+        };
+
+        TileOverlay {
+            caption,
+            badges: badges_for(item),
+            progress,
+            rendered: OnceCell::new(),
+        }
+    }
+
+    fn rendered(&self) -> &RenderedOverlay {
+        self.rendered.get_or_init(|| RenderedOverlay {
+            caption: self
+                .caption
+                .as_ref()
+                .map(|caption| app_gl::render_sized_text_to_texture(caption, CAPTION_CHAR_SIZE)),
+            badges: self
+                .badges
+                .iter()
+                .map(|(text, color)| Badge {
+                    text: app_gl::render_sized_text_to_texture(text, BADGE_CHAR_SIZE),
+                    color: *color,
+                })
+                .collect(),
+        })
+    }
+}
+
+// Draws the overlay of a tile already drawn at `rect`
+pub fn draw(app: &App, c_idx: usize, t_idx: usize, rect: &Rect, window_size: &(u32, u32)) {
+    let container = &app.containers[c_idx];
+    let image = &container.images[t_idx];
+    let overlay = &image.overlay;
+    let alpha = image.alpha * container.alpha;
+    // Nothing would show, so leave the text unrendered
+    if alpha <= 0. {
+        return;
+    }
+    let rendered = overlay.rendered();
+    let left = rect.x - rect.width / 2.;
+    let top = rect.y + rect.height / 2.;
+    let bottom = rect.y - rect.height / 2.;
+
+    // Badges run along the top left corner
+    let mut badge_left = left + BADGE_MARGIN;
+    for badge in &rendered.badges {
+        let width = badge.text.width as f32 + BADGE_PADDING.0 * 2.;
+        let height = badge.text.height as f32 + BADGE_PADDING.1 * 2.;
+        let background = Rect {
+            x: badge_left + width / 2.,
+            y: top - BADGE_MARGIN - height / 2.,
+            width,
+            height,
+        };
+        let [r, g, b, a] = badge.color;
+        app_gl::draw_color_rect(app, &background, [r, g, b, a * alpha], window_size);
+        let text_rect = Rect {
+            width: badge.text.width as f32,
+            height: badge.text.height as f32,
+            ..background
+        };
+        app_gl::draw_text(app, &text_rect, badge.text.texture_id, alpha, window_size);
+        badge_left += width + BADGE_MARGIN / 2.;
+    }
+
+    if let Some(progress) = overlay.progress {
+        let track = Rect {
+            x: rect.x,
+            y: bottom + PROGRESS_BAR_MARGIN,
+            width: rect.width - PROGRESS_BAR_MARGIN * 2.,
+            height: PROGRESS_BAR_HEIGHT,
+        };
+        let [r, g, b, a] = PROGRESS_TRACK_COLOR;
+        app_gl::draw_color_rect(app, &track, [r, g, b, a * alpha], window_size);
+        let fill = Rect {
+            x: track.x - track.width / 2. + track.width * progress / 2.,
+            width: track.width * progress,
+            ..track
+        };
+        let [r, g, b, a] = PROGRESS_FILL_COLOR;
+        app_gl::draw_color_rect(app, &fill, [r, g, b, a * alpha], window_size);
+    }
+
+    // Captions fade in with the focus zoom so they follow the tile's own animation
+    if let Some(caption) = &rendered.caption {
+        let zoom = container.style.focus_scale() - 1.;
+        let focus = util::clamp((image.scale - 1.) / zoom, 0., 1.);
+        if focus > 0. {
+            let caption_rect = Rect {
+                x: left + caption.width as f32 / 2.,
+                y: bottom - CAPTION_GAP,
+                width: caption.width as f32,
+                height: caption.height as f32,
+            };
+            app_gl::draw_text(app, &caption_rect, caption.texture_id, focus * alpha, window_size);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+// How long shutting down waits for tasks already under way before abandoning them
+static SHUTDOWN_GRACE: Duration = Duration::from_millis(200);
+
+// Shared flag telling workers to stop at their next check; clones share the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// Fixed set of named worker threads running async tasks, cancelled and stopped together
+#[derive(Default)]
+pub struct WorkerPool {
+    runtime: Option<Runtime>,
+    cancel: CancelToken,
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl WorkerPool {
+    pub fn start(name: &str, workers: usize) -> Result<WorkerPool, String> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(workers.max(1))
+            .thread_name(name)
+            .enable_all()
+            .build()
+            .map_err(|err| format!("Failed to start {} workers: {}", name, err))?;
+        Ok(WorkerPool {
+            runtime: Some(runtime),
+            cancel: CancelToken::default(),
+        })
+    }
+
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        if let Some(runtime) = &self.runtime {
+            runtime.spawn(task);
+        }
+    }
+
+    // Cancels every task and stops the workers. Tasks still waiting on a request after a short grace are
+    // abandoned rather than waited out, so quitting never hangs on a slow server.
+    pub fn shutdown(&mut self) {
+        self.cancel.cancel();
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_timeout(SHUTDOWN_GRACE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn shutdown_cancels_and_abandons_slow_tasks() {
+        let mut pool = WorkerPool::start("pool-test", 2).unwrap();
+        let cancel = pool.cancel_token();
+        pool.spawn(async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+        pool.spawn(async {
+            // A blocking call stands in for a request stuck waiting on its timeout
+            let _ = tokio::task::spawn_blocking(|| std::thread::sleep(Duration::from_secs(10))).await;
+        });
+
+        let started = Instant::now();
+        pool.shutdown();
+        assert!(cancel.is_cancelled());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn nothing_runs_after_shutdown() {
+        let mut pool = WorkerPool::start("pool-test", 1).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let sender = tx.clone();
+        pool.spawn(async move {
+            let _ = sender.send(1);
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(1));
+
+        pool.shutdown();
+        // Spawning after shutdown is quietly dropped
+        pool.spawn(async move {
+            let _ = tx.send(2);
+        });
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
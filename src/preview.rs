@@ -0,0 +1,282 @@
+use sfml::graphics::Image;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::time::SystemTime;
+
+use crate::app_gl;
+
+static CACHE_DIR: &str = "cache/previews";
+// Previews kept on disk, about 2.5 KB each; the least recently used beyond this go at startup
+static MAX_CACHED_PREVIEWS: usize = 4000;
+// Width of previews decoded or cached; height follows the artwork's aspect ratio
+static PREVIEW_WIDTH: u32 = 32;
+static BLUR_HASH_CHARS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+static BASE64_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Small RGBA image shown in place of a tile's artwork until the real thing arrives
+#[derive(Debug)]
+pub struct Preview {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Preview {
+    pub fn upload(&self) -> u32 {
+        app_gl::upload_rgba(self.width, self.height, &self.pixels)
+    }
+}
+
+fn decode_base83(chars: &str) -> Result<u32, String> {
+    chars.chars().try_fold(0, |value, c| match BLUR_HASH_CHARS.find(c) {
+        Some(digit) => Ok(value * 83 + digit as u32),
+        None => Err(format!("Invalid BlurHash character: {:?}", c)),
+    })
+}
+
+fn srgb_to_linear(value: u32) -> f32 {
+    let v = value as f32 / 255.;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0., 1.);
+    if v <= 0.003_130_8 {
+        (v * 12.92 * 255. + 0.5) as u8
+    } else {
+        ((1.055 * v.powf(1. / 2.4) - 0.055) * 255. + 0.5) as u8
+    }
+}
+
+fn sign_pow(value: f32, exp: f32) -> f32 {
+    value.abs().powf(exp).copysign(value)
+}
+
+// Decodes a BlurHash (https://blurha.sh) into a preview of the given size
+pub fn decode_blur_hash(hash: &str, width: u32, height: u32) -> Result<Preview, String> {
+    if !hash.is_ascii() || hash.len() < 6 {
+        return Err(format!("BlurHash too short: {:?}", hash));
+    }
+
+    let size_flag = decode_base83(&hash[0..1])?;
+    let (components_x, components_y) = ((size_flag % 9 + 1) as usize, (size_flag / 9 + 1) as usize);
+    if hash.len() != 4 + 2 * components_x * components_y {
+        return Err(format!("BlurHash has the wrong length for its components: {:?}", hash));
+    }
+
+    let maximum_value = (decode_base83(&hash[1..2])? + 1) as f32 / 166.;
+    let mut colors = Vec::with_capacity(components_x * components_y);
+    let dc = decode_base83(&hash[2..6])?;
+    colors.push([srgb_to_linear(dc >> 16), srgb_to_linear((dc >> 8) & 255), srgb_to_linear(dc & 255)]);
+    for component in 1..components_x * components_y {
+        let ac = decode_base83(&hash[4 + component * 2..6 + component * 2])?;
+        let quantised = [ac / (19 * 19), (ac / 19) % 19, ac % 19];
+        colors.push([0, 1, 2].map(|channel| sign_pow((quantised[channel] as f32 - 9.) / 9., 2.) * maximum_value));
+    }
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let mut color = [0.; 3];
+            for j in 0..components_y {
+                for i in 0..components_x {
+                    let basis = (std::f32::consts::PI * x as f32 * i as f32 / width as f32).cos()
+                        * (std::f32::consts::PI * y as f32 * j as f32 / height as f32).cos();
+                    let component = colors[i + j * components_x];
+                    for channel in 0..3 {
+                        color[channel] += component[channel] * basis;
+                    }
+                }
+            }
+            pixels.extend_from_slice(&[linear_to_srgb(color[0]), linear_to_srgb(color[1]), linear_to_srgb(color[2]), 255]);
+        }
+    }
+
+    Ok(Preview { width, height, pixels })
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let digit = BASE64_CHARS.find(c).ok_or(format!("Invalid base64 character: {:?}", c))?;
+        buffer = (buffer << 6) | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+// Averages the image down to preview width
+fn downscale(image: &Image) -> Preview {
+    let size = image.size();
+    let width = PREVIEW_WIDTH.min(size.x).max(1);
+    let height = ((size.y as f32 * width as f32 / size.x.max(1) as f32).round() as u32).max(1);
+    let source = image.pixel_data();
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let (x0, x1) = (x * size.x / width, ((x + 1) * size.x / width).max(x * size.x / width + 1));
+            let (y0, y1) = (y * size.y / height, ((y + 1) * size.y / height).max(y * size.y / height + 1));
+            let mut sum = [0u32; 4];
+            for sy in y0..y1.min(size.y) {
+                for sx in x0..x1.min(size.x) {
+                    let offset = ((sy * size.x + sx) * 4) as usize;
+                    for channel in 0..4 {
+                        sum[channel] += source[offset + channel] as u32;
+                    }
+                }
+            }
+            let count = ((x1.min(size.x) - x0) * (y1.min(size.y) - y0)).max(1);
+            pixels.extend(sum.iter().map(|e| (e / count) as u8));
+        }
+    }
+
+    Preview { width, height, pixels }
+}
+
+// Preview published in the item data: a base64 data url or a BlurHash
+pub fn from_item_value(value: &str, aspect_ratio: f32) -> Result<Preview, String> {
+    if let Some(data_url) = value.strip_prefix("data:") {
+        let encoded = data_url
+            .split(',')
+            .nth(1)
+            .ok_or(format!("Malformed data url preview: {:?}", value))?;
+        let image = Image::from_memory(&decode_base64(encoded)?).ok_or("Undecodable data url preview".to_string())?;
+        return Ok(downscale(&image));
+    }
+
+    let height = ((PREVIEW_WIDTH as f32 / aspect_ratio).round() as u32).max(1);
+    decode_blur_hash(value, PREVIEW_WIDTH, height)
+}
+
+fn cache_path(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{}/{:016x}.rgba", CACHE_DIR, hasher.finish())
+}
+
+// Cached files hold the width and height as little endian u32s followed by the RGBA pixels
+fn parse_cached(mut bytes: Vec<u8>) -> Option<Preview> {
+    if bytes.len() < 8 {
+        return None;
+    }
+
+    let width = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let height = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    // A corrupt header is just a miss
+    let expected_len = (width as usize).checked_mul(height as usize)?.checked_mul(4)?;
+    let pixels = bytes.split_off(8);
+    if pixels.len() != expected_len {
+        return None;
+    }
+    Some(Preview { width, height, pixels })
+}
+
+pub fn load_from_cache(url: &str) -> Option<Preview> {
+    let mut f = File::options().read(true).write(true).open(cache_path(url)).ok()?;
+    let mut bytes = Vec::new();
+    f.read_to_end(&mut bytes).ok()?;
+    // Marks the preview as recently used so pruning keeps it
+    let _ = f.set_modified(SystemTime::now());
+    parse_cached(bytes)
+}
+
+// Deletes the least recently used previews once the cache holds more than MAX_CACHED_PREVIEWS
+pub fn prune_cache() {
+    let entries = match fs::read_dir(CACHE_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut files: Vec<(SystemTime, std::path::PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    if files.len() <= MAX_CACHED_PREVIEWS {
+        return;
+    }
+
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in files.drain(MAX_CACHED_PREVIEWS..) {
+        if let Err(err) = fs::remove_file(&path) {
+            println!("Failed to remove cached preview {:?}: {:?}", path, err);
+        }
+    }
+}
+
+// Keeps a preview of freshly loaded artwork so the next launch has something to show straight away
+pub fn store_in_cache(url: &str, image: &Image) -> Result<(), String> {
+    let path = cache_path(url);
+    if fs::metadata(&path).is_ok() {
+        return Ok(());
+    }
+
+    let preview = downscale(image);
+    fs::create_dir_all(CACHE_DIR).map_err(|err| format!("Failed to create preview cache {}: {:?}", CACHE_DIR, err))?;
+    let mut f = File::create(&path).map_err(|err| format!("Failed to create preview {}: {:?}", path, err))?;
+    f.write_all(&preview.width.to_le_bytes())
+        .and_then(|_| f.write_all(&preview.height.to_le_bytes()))
+        .and_then(|_| f.write_all(&preview.pixels))
+        .map_err(|err| format!("Failed to write preview {}: {:?}", path, err))
+}
+
+// Stand-in for a tile until its artwork loads, from the item data or else the disk cache. Reads and decodes,
+// so it runs on the loading pool.
+pub fn load(item_preview: Option<&str>, url: &str, aspect_ratio: f32) -> Option<Preview> {
+    match item_preview.map(|value| from_item_value(value, aspect_ratio)) {
+        Some(Ok(preview)) => Some(preview),
+        Some(Err(err)) => {
+            println!("{}", err);
+            load_from_cache(url)
+        }
+        None => load_from_cache(url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(width: u32, height: u32, pixel_bytes: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.resize(8 + pixel_bytes, 255);
+        bytes
+    }
+
+    #[test]
+    fn reads_cached_previews() {
+        let preview = parse_cached(cached(2, 3, 24)).unwrap();
+        assert_eq!((preview.width, preview.height, preview.pixels.len()), (2, 3, 24));
+    }
+
+    #[test]
+    fn truncated_or_corrupt_files_are_misses() {
+        assert!(parse_cached(vec![1, 0, 0]).is_none());
+        assert!(parse_cached(cached(2, 3, 20)).is_none());
+        assert!(parse_cached(cached(u32::MAX, u32::MAX, 16)).is_none());
+    }
+
+    #[test]
+    fn decodes_blur_hashes() {
+        let preview = decode_blur_hash("LEHV6nWB2yk8pyo0adR*.7kCMdnj", 8, 6).unwrap();
+        assert_eq!(preview.pixels.len(), 8 * 6 * 4);
+        assert!(decode_blur_hash("LEHV6nWB2yk8", 8, 6).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::input::Action;
+
+// Hold time before the first repeat fires
+static INITIAL_DELAY: f32 = 0.35;
+static REPEAT_INTERVAL: f32 = 0.12;
+static MIN_REPEAT_INTERVAL: f32 = 0.03;
+// Hold time after which repeats start speeding up
+static ACCELERATION_DELAY: f32 = 1.2;
+// Seconds of sustained holding for the repeat interval to halve
+static ACCELERATION_HALF_LIFE: f32 = 1.;
+
+#[derive(Debug)]
+struct Held {
+    action: Action,
+    held_for: f32,
+    next_fire: f32,
+}
+
+// Generates repeats for a held navigation action at our own rate instead of the OS key repeat
+#[derive(Debug, Default)]
+pub struct KeyRepeat {
+    held: Option<Held>,
+}
+
+pub fn is_repeatable(action: Action) -> bool {
+    matches!(action, Action::Up | Action::Down | Action::Left | Action::Right)
+}
+
+fn interval_after(held_for: f32) -> f32 {
+    if held_for < ACCELERATION_DELAY {
+        return REPEAT_INTERVAL;
+    }
+
+    let halvings = (held_for - ACCELERATION_DELAY) / ACCELERATION_HALF_LIFE;
+    (REPEAT_INTERVAL * 0.5f32.powf(halvings)).max(MIN_REPEAT_INTERVAL)
+}
+
+impl KeyRepeat {
+    pub fn press(&mut self, action: Action) {
+        if !is_repeatable(action) {
+            return;
+        }
+
+        self.held = Some(Held {
+            action,
+            held_for: 0.,
+            next_fire: INITIAL_DELAY,
+        });
+    }
+
+    pub fn release(&mut self, action: Action) {
+        if matches!(&self.held, Some(held) if held.action == action) {
+            self.held = None;
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.held = None;
+    }
+
+    // Advances the hold and returns how many repeats fired, coalesced into a single action
+    pub fn tick(&mut self, dt: f32) -> Option<(Action, i32)> {
+        let held = self.held.as_mut()?;
+        held.held_for += dt;
+
+        let mut count = 0;
+        while held.held_for >= held.next_fire {
+            held.next_fire += interval_after(held.next_fire);
+            count += 1;
+        }
+
+        if count > 0 {
+            Some((held.action, count))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_repeats_before_the_initial_delay() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::Down);
+        assert_eq!(repeat.tick(INITIAL_DELAY - 0.05), None);
+        assert_eq!(repeat.tick(0.1), Some((Action::Down, 1)));
+        // Then one every interval, counted from when the repeat was due rather than when the tick landed
+        assert_eq!(repeat.tick(REPEAT_INTERVAL - 0.1), None);
+        assert_eq!(repeat.tick(0.1), Some((Action::Down, 1)));
+    }
+
+    #[test]
+    fn long_ticks_fire_several_repeats_at_once() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::Right);
+        assert_eq!(repeat.tick(INITIAL_DELAY + REPEAT_INTERVAL * 3.5), Some((Action::Right, 4)));
+    }
+
+    #[test]
+    fn repeats_speed_up_the_longer_a_key_is_held() {
+        assert_eq!(interval_after(0.), REPEAT_INTERVAL);
+        assert_eq!(interval_after(ACCELERATION_DELAY), REPEAT_INTERVAL);
+        assert_eq!(interval_after(ACCELERATION_DELAY + ACCELERATION_HALF_LIFE), REPEAT_INTERVAL / 2.);
+        assert_eq!(interval_after(ACCELERATION_DELAY + 10.), MIN_REPEAT_INTERVAL);
+
+        // A second of holding well past the delay fires more than the first second did
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::Up);
+        let early = repeat.tick(1.).unwrap().1;
+        repeat.tick(ACCELERATION_DELAY + ACCELERATION_HALF_LIFE);
+        let late = repeat.tick(1.).unwrap().1;
+        assert!(late > early);
+    }
+
+    #[test]
+    fn only_navigation_repeats_until_released() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::Select);
+        assert_eq!(repeat.tick(1.), None);
+
+        repeat.press(Action::Left);
+        repeat.release(Action::Right);
+        assert!(repeat.tick(1.).is_some());
+        repeat.release(Action::Left);
+        assert_eq!(repeat.tick(1.), None);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use crate::item::Item;
+
+// How a container lays out its tiles
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RowStyle {
+    // Scrolling row of 16:9 tiles
+    Landscape,
+    // Wide banners shown one at a time that advance on their own
+    Hero,
+    // Scrolling row of portrait posters
+    Poster,
+    // Tiles wrapped onto several lines instead of scrolling
+    Grid,
+    // Small studio or brand tiles
+    Brand,
+}
+
+// Where a scrolling row keeps its focused tile
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RowAlign {
+    // Focused tile pinned to the row's left edge
+    Left,
+    // Only scroll once focus would leave the visible tiles
+    Edge,
+    // Focused tile in the middle of the window
+    Centered,
+}
+
+static ALIGN_NAMES: [(&str, RowAlign); 3] = [("Left", RowAlign::Left), ("Edge", RowAlign::Edge), ("Centered", RowAlign::Centered)];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RowScrolling {
+    pub align: RowAlign,
+    // Moving past either end continues from the other, with the tiles repeating around the row
+    pub wrap: bool,
+}
+
+static STYLE_NAMES: [(&str, RowStyle); 5] = [
+    ("Landscape", RowStyle::Landscape),
+    ("Hero", RowStyle::Hero),
+    ("Poster", RowStyle::Poster),
+    ("Grid", RowStyle::Grid),
+    ("Brand", RowStyle::Brand),
+];
+
+impl RowStyle {
+    pub fn from_name(name: &str) -> Option<RowStyle> {
+        STYLE_NAMES.iter().find(|(n, _)| *n == name).map(|(_, s)| *s)
+    }
+
+    // Artwork size before focus scaling at the default landscape tile size; layout scales it to the configured one
+    pub fn tile_size(&self) -> (f32, f32) {
+        match self {
+            RowStyle::Landscape => (500., 281.),
+            RowStyle::Hero => (1500., 384.),
+            RowStyle::Poster => (240., 338.),
+            RowStyle::Grid => (300., 169.),
+            RowStyle::Brand => (300., 169.),
+        }
+    }
+
+    // Horizontal distance between neighbouring tile centers
+    pub fn tile_spacing(&self) -> f32 {
+        match self {
+            RowStyle::Landscape => 625.,
+            RowStyle::Hero => 1600.,
+            RowStyle::Poster => 290.,
+            RowStyle::Grid => 340.,
+            RowStyle::Brand => 340.,
+        }
+    }
+
+    // Tiles per line for styles that wrap rather than scroll
+    pub fn columns(&self) -> Option<usize> {
+        match self {
+            RowStyle::Grid => Some(5),
+            _ => None,
+        }
+    }
+
+    pub fn default_scrolling(&self) -> RowScrolling {
+        RowScrolling {
+            align: RowAlign::Left,
+            // Carousels cycle round rather than stopping at the last banner
+            wrap: *self == RowStyle::Hero,
+        }
+    }
+
+    pub fn focus_scale(&self) -> f32 {
+        match self {
+            // Banners are already most of the screen wide
+            RowStyle::Hero => 1.04,
+            _ => 1.2,
+        }
+    }
+
+    // Hero banners and brand logos already carry their name in the artwork
+    pub fn shows_captions(&self) -> bool {
+        !matches!(self, RowStyle::Hero | RowStyle::Brand)
+    }
+
+    // Image variants that suit the tile shape, as (kind, aspect ratio), in order of preference
+    fn image_variants(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            RowStyle::Landscape | RowStyle::Grid | RowStyle::Brand => &[("tile", "1.78")],
+            RowStyle::Hero => &[("hero_tile", "3.91"), ("tile", "3.91")],
+            RowStyle::Poster => &[("tile", "0.71"), ("tile", "0.75")],
+        }
+    }
+
+    // Url of the artwork to load for an item shown in this style, falling back to its default tile
+    pub fn image_url(&self, item: &Item) -> String {
+        self.image_variants()
+            .iter()
+            .find_map(|(kind, aspect_ratio)| item.image_url(kind, aspect_ratio))
+            .unwrap_or_else(|| item.tile_url.clone())
+    }
+
+    pub fn image_preview(&self, item: &Item) -> Option<String> {
+        self.image_variants()
+            .iter()
+            .find_map(|(kind, aspect_ratio)| item.image_preview(kind, aspect_ratio))
+    }
+}
+
+// Chooses a style per container, keyed by the container's style, set type, reference type or title
+#[derive(Debug)]
+pub struct RowStyles {
+    styles: HashMap<String, RowStyle>,
+    // Scrolling that differs from the style's default
+    scrolling: HashMap<String, RowScrolling>,
+}
+
+impl Default for RowStyles {
+    fn default() -> Self {
+        let defaults = [
+            ("hero", RowStyle::Hero),
+            ("brand", RowStyle::Brand),
+            ("TrendingSet", RowStyle::Poster),
+            ("Collections", RowStyle::Grid),
+        ];
+
+        RowStyles {
+            styles: defaults.iter().map(|(k, s)| (k.to_string(), *s)).collect(),
+            scrolling: HashMap::new(),
+        }
+    }
+}
+
+impl RowStyles {
+    // Loads a style map of the form { "TrendingSet": "Poster", ... } on top of the defaults. Entries can also
+    // set scrolling, as in { "hero": { "style": "Hero", "align": "Centered", "wrap": true } }
+    pub fn load_from_disk(path: &str) -> Result<RowStyles, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open row styles file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read row styles file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed row styles file {}: {:?}", path, err))?;
+        let entries = data.as_object().ok_or(format!("Row styles file {} should be an object", path))?;
+
+        let mut styles = RowStyles::default();
+        for (key, entry) in entries {
+            let style_name = entry.as_str().or_else(|| entry["style"].as_str()).unwrap_or("");
+            let style = RowStyle::from_name(style_name).ok_or(format!("Unknown row style for {}: {:?}", key, style_name))?;
+            styles.styles.insert(key.to_string(), style);
+
+            if entry.is_object() && (entry.get("align").is_some() || entry.get("wrap").is_some()) {
+                let mut scrolling = style.default_scrolling();
+                if let Some(align_name) = entry["align"].as_str() {
+                    scrolling.align = ALIGN_NAMES
+                        .iter()
+                        .find(|(n, _)| *n == align_name)
+                        .map(|(_, a)| *a)
+                        .ok_or(format!("Unknown row alignment for {}: {:?}", key, align_name))?;
+                }
+                scrolling.wrap = entry["wrap"].as_bool().unwrap_or(scrolling.wrap);
+                styles.scrolling.insert(key.to_string(), scrolling);
+            }
+        }
+
+        Ok(styles)
+    }
+
+    pub fn style_for(&self, container: &serde_json::Value, title: &str) -> RowStyle {
+        keys(container, title)
+            .iter()
+            .flatten()
+            .find_map(|key| self.styles.get(*key).copied())
+            .unwrap_or(RowStyle::Landscape)
+    }
+
+    pub fn scrolling_for(&self, container: &serde_json::Value, title: &str) -> RowScrolling {
+        keys(container, title)
+            .iter()
+            .flatten()
+            .find_map(|key| self.scrolling.get(*key).copied())
+            .unwrap_or_else(|| self.style_for(container, title).default_scrolling())
+    }
+}
+
+// Container fields a style can be keyed by, most specific first
+fn keys<'a>(container: &'a serde_json::Value, title: &'a str) -> [Option<&'a str>; 4] {
+    [
+        container["style"].as_str(),
+        container["set"]["type"].as_str(),
+        container["set"]["refType"].as_str(),
+        Some(title),
+    ]
+}
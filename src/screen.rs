@@ -0,0 +1,204 @@
+use sfml::window::Event;
+
+use crate::input::Action;
+use crate::sound::SoundEvent;
+use crate::tween::{Property, Tween};
+use crate::App;
+
+// Rate at which enter and exit transitions advance from 0 to 1 per second
+static TRANSITION_SPEED: f32 = 3.;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScreenKind {
+    Home,
+    Detail,
+    Search,
+    Settings,
+    Error,
+}
+
+// What a screen asks of the stack after handling input
+pub enum Transition {
+    None,
+    Push(Box<dyn Screen>),
+    Pop,
+}
+
+pub trait Screen {
+    fn kind(&self) -> ScreenKind;
+
+    // Opaque screens hide everything underneath, so lower screens are skipped when drawing
+    fn is_opaque(&self) -> bool {
+        false
+    }
+
+    fn handle_action(&mut self, app: &mut App, action: Action) -> Transition;
+
+    // Held-key repeats arrive coalesced; screens that can apply a burst in one go override this
+    fn handle_repeated_action(&mut self, app: &mut App, action: Action, count: i32) -> Transition {
+        for _ in 0..count {
+            match self.handle_action(app, action) {
+                Transition::None => {}
+                transition => return transition,
+            }
+        }
+        Transition::None
+    }
+
+    fn map_mouse_event(&mut self, _app: &mut App, _event: &Event, _window_size: &(u32, u32)) -> Option<Action> {
+        None
+    }
+
+    // Screens taking typed text get printable keys as text instead of as bound actions
+    fn captures_text(&self) -> bool {
+        false
+    }
+
+    fn handle_text(&mut self, _app: &mut App, _unicode: char) {}
+
+    fn update(&mut self, _app: &mut App, _dt: f32) {}
+
+    // Called once the exit transition has finished, right before the screen is dropped
+    fn on_exit(&mut self, _app: &mut App) {}
+
+    // `progress` runs from 0 to 1 while entering and back to 0 while exiting
+    fn render(&self, app: &App, progress: f32, window_size: &(u32, u32));
+}
+
+pub struct ScreenEntry {
+    pub id: usize,
+    pub screen: Box<dyn Screen>,
+    pub progress: f32,
+    pub is_exiting: bool,
+}
+
+#[derive(Default)]
+pub struct ScreenStack {
+    entries: Vec<ScreenEntry>,
+    next_id: usize,
+}
+
+impl ScreenStack {
+    // Screen currently receiving input
+    pub fn active_kind(&self) -> Option<ScreenKind> {
+        self.entries.iter().rev().find(|e| !e.is_exiting).map(|e| e.screen.kind())
+    }
+
+    pub fn progress(&self, id: usize) -> Option<f32> {
+        self.entries.iter().find(|e| e.id == id).map(|e| e.progress)
+    }
+
+    pub fn set_progress(&mut self, id: usize, progress: f32) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.progress = progress;
+        }
+    }
+}
+
+pub fn push(app: &mut App, screen: Box<dyn Screen>) {
+    let id = app.screens.next_id;
+    app.screens.next_id += 1;
+
+    // The root screen has nothing to transition from
+    let progress = if app.screens.entries.is_empty() { 1. } else { 0. };
+    app.screens.entries.push(ScreenEntry {
+        id,
+        screen,
+        progress,
+        is_exiting: false,
+    });
+
+    if progress < 1. {
+        app.tweens
+            .start(Tween::new(Property::ScreenProgress(id), 0., 1.).with_speed(TRANSITION_SPEED));
+    }
+}
+
+// Starts the exit transition of the active screen; the root screen is never popped
+pub fn pop(app: &mut App) {
+    let active: Vec<usize> = app.screens.entries.iter().filter(|e| !e.is_exiting).map(|e| e.id).collect();
+    if active.len() <= 1 {
+        return;
+    }
+
+    let id = active[active.len() - 1];
+    if let Some(entry) = app.screens.entries.iter_mut().find(|e| e.id == id) {
+        entry.is_exiting = true;
+        let from = entry.progress;
+        app.tweens.start(
+            Tween::new(Property::ScreenProgress(id), from, 0.)
+                .with_speed(TRANSITION_SPEED)
+                .on_complete(remove_exited),
+        );
+    }
+}
+
+fn remove_exited(app: &mut App) {
+    while let Some(idx) = app.screens.entries.iter().position(|e| e.is_exiting && e.progress <= 0.) {
+        let mut entry = app.screens.entries.remove(idx);
+        entry.screen.on_exit(app);
+    }
+}
+
+fn apply(app: &mut App, transition: Transition) {
+    match transition {
+        Transition::None => {}
+        Transition::Push(screen) => {
+            app.sounds.play(SoundEvent::Select);
+            push(app, screen);
+        }
+        Transition::Pop => {
+            app.sounds.play(SoundEvent::Back);
+            pop(app);
+        }
+    }
+}
+
+// Runs `f` on the active screen with the stack detached from `app`, so the screen can freely borrow the app
+fn with_active_screen<R>(app: &mut App, f: impl FnOnce(&mut dyn Screen, &mut App) -> R) -> Option<R> {
+    let mut entries = std::mem::take(&mut app.screens.entries);
+    let result = entries.iter_mut().rev().find(|e| !e.is_exiting).map(|e| f(e.screen.as_mut(), app));
+    app.screens.entries = entries;
+    result
+}
+
+pub fn handle_action(app: &mut App, action: Action) {
+    if let Some(transition) = with_active_screen(app, |screen, app| screen.handle_action(app, action)) {
+        apply(app, transition);
+    }
+}
+
+pub fn handle_repeated_action(app: &mut App, action: Action, count: i32) {
+    if let Some(transition) = with_active_screen(app, |screen, app| screen.handle_repeated_action(app, action, count)) {
+        apply(app, transition);
+    }
+}
+
+pub fn map_mouse_event(app: &mut App, event: &Event, window_size: &(u32, u32)) -> Option<Action> {
+    with_active_screen(app, |screen, app| screen.map_mouse_event(app, event, window_size)).flatten()
+}
+
+pub fn captures_text(app: &App) -> bool {
+    matches!(app.screens.entries.iter().rev().find(|e| !e.is_exiting), Some(entry) if entry.screen.captures_text())
+}
+
+pub fn handle_text(app: &mut App, unicode: char) {
+    with_active_screen(app, |screen, app| screen.handle_text(app, unicode));
+}
+
+pub fn update(app: &mut App, dt: f32) {
+    let mut entries = std::mem::take(&mut app.screens.entries);
+    for entry in &mut entries {
+        entry.screen.update(app, dt);
+    }
+    app.screens.entries = entries;
+}
+
+// Draws from the topmost opaque screen upwards
+pub fn render(app: &App, window_size: &(u32, u32)) {
+    let entries = &app.screens.entries;
+    let first = entries.iter().rposition(|e| e.screen.is_opaque() && e.progress >= 1.).unwrap_or(0);
+    for entry in &entries[first..] {
+        entry.screen.render(app, entry.progress, window_size);
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs::File;
+use std::io::Read;
+
+use crate::screen::ScreenKind;
+use crate::util;
+
+// Space kept between the focused item and the edge of the view when scrolling by the minimum amount
+static EDGE_MARGIN: f32 = 60.;
+static DEFAULT_ANCHOR: f32 = 0.3;
+
+// Where a page scrolls to when focus moves vertically
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScrollPolicy {
+    // Focused line at the top of the page
+    Top,
+    // Focused line in the middle of the view
+    Centered,
+    // Focused line at a fixed fraction of the view height from its top
+    Anchor(f32),
+    // Only scroll when the focused line would leave the view
+    Edge,
+}
+
+impl ScrollPolicy {
+    fn from_name(name: &str, anchor: f32) -> Option<ScrollPolicy> {
+        match name {
+            "Top" => Some(ScrollPolicy::Top),
+            "Centered" => Some(ScrollPolicy::Centered),
+            "Anchor" => Some(ScrollPolicy::Anchor(anchor)),
+            "Edge" => Some(ScrollPolicy::Edge),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScrollPolicy::Top => "Top",
+            ScrollPolicy::Centered => "Centered",
+            ScrollPolicy::Anchor(_) => "Anchor",
+            ScrollPolicy::Edge => "Edge",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PageScroll {
+    pub policy: ScrollPolicy,
+    // How much of the next line stays visible below the focused one, hinting there is more to see
+    pub peek: f32,
+}
+
+impl Default for PageScroll {
+    fn default() -> Self {
+        PageScroll {
+            policy: ScrollPolicy::Top,
+            peek: 0.,
+        }
+    }
+}
+
+// Visible part of a page when its scroll is 0, as a distance down from the page top and a height
+#[derive(Debug, Copy, Clone)]
+pub struct View {
+    pub top: f32,
+    pub height: f32,
+}
+
+// Scroll position that shows the band (top and bottom distances down the page) according to the policy.
+// `current` is where scrolling is heading now, which edge scrolling moves as little as possible from.
+pub fn scroll_to(page: &PageScroll, current: f32, band: (f32, f32), view: &View) -> f32 {
+    let (top, bottom) = band;
+    let view_bottom = view.top + view.height;
+    let scroll = match page.policy {
+        ScrollPolicy::Top => top,
+        ScrollPolicy::Centered => (top + bottom) / 2. - (view.top + view.height / 2.),
+        ScrollPolicy::Anchor(anchor) => top - (view.top + view.height * anchor),
+        ScrollPolicy::Edge => current
+            .max(bottom + page.peek + EDGE_MARGIN - view_bottom)
+            .min(top - EDGE_MARGIN - view.top),
+    };
+
+    // Make room for the peek, unless that would push the focused line out at the top
+    let scroll = scroll.max(bottom + page.peek - view_bottom).min(scroll.max(top - view.top));
+    // Never scroll above the start of the page
+    scroll.max(0.)
+}
+
+static SCREEN_NAMES: [(&str, ScreenKind); 2] = [("Home", ScreenKind::Home), ("Search", ScreenKind::Search)];
+
+// Scroll behaviour of each screen that scrolls vertically
+#[derive(Debug)]
+pub struct ScrollSettings {
+    screens: Vec<(ScreenKind, PageScroll)>,
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        ScrollSettings {
+            screens: vec![
+                (
+                    ScreenKind::Home,
+                    PageScroll {
+                        policy: ScrollPolicy::Top,
+                        peek: 120.,
+                    },
+                ),
+                (
+                    ScreenKind::Search,
+                    PageScroll {
+                        policy: ScrollPolicy::Edge,
+                        peek: 60.,
+                    },
+                ),
+            ],
+        }
+    }
+}
+
+impl ScrollSettings {
+    // Loads settings of the form { "Home": { "policy": "Anchor", "anchor": 0.3, "peek": 120 }, ... } on top of the defaults
+    pub fn load_from_disk(path: &str) -> Result<ScrollSettings, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open scroll settings file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read scroll settings file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed scroll settings file {}: {:?}", path, err))?;
+        let entries = data
+            .as_object()
+            .ok_or(format!("Scroll settings file {} should be an object", path))?;
+
+        let mut settings = ScrollSettings::default();
+        for (screen_name, entry) in entries {
+            let kind = SCREEN_NAMES
+                .iter()
+                .find(|(n, _)| n == screen_name)
+                .map(|(_, k)| *k)
+                .ok_or(format!("Unknown screen in scroll settings: {:?}", screen_name))?;
+            let mut page = settings.for_screen(kind);
+            if let Some(policy_name) = entry["policy"].as_str() {
+                let anchor = util::clamp(entry["anchor"].as_f64().unwrap_or(DEFAULT_ANCHOR as f64) as f32, 0., 1.);
+                page.policy = ScrollPolicy::from_name(policy_name, anchor)
+                    .ok_or(format!("Unknown scroll policy for {}: {:?}", screen_name, policy_name))?;
+            }
+            if let Some(peek) = entry["peek"].as_f64() {
+                page.peek = peek.max(0.) as f32;
+            }
+            settings.set(kind, page);
+        }
+
+        Ok(settings)
+    }
+
+    pub fn for_screen(&self, kind: ScreenKind) -> PageScroll {
+        self.screens
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, page)| *page)
+            .unwrap_or_default()
+    }
+
+    pub fn set(&mut self, kind: ScreenKind, page: PageScroll) {
+        self.screens.retain(|(k, _)| *k != kind);
+        self.screens.push((kind, page));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static VIEW: View = View { top: 0., height: 1000. };
+    static BAND: (f32, f32) = (2000., 2300.);
+
+    fn page(policy: ScrollPolicy, peek: f32) -> PageScroll {
+        PageScroll { policy, peek }
+    }
+
+    #[test]
+    fn each_policy_places_the_band() {
+        assert_eq!(scroll_to(&page(ScrollPolicy::Top, 0.), 0., BAND, &VIEW), 2000.);
+        assert_eq!(scroll_to(&page(ScrollPolicy::Centered, 0.), 0., BAND, &VIEW), 1650.);
+        assert_eq!(scroll_to(&page(ScrollPolicy::Anchor(0.3), 0.), 0., BAND, &VIEW), 1700.);
+    }
+
+    #[test]
+    fn edge_scrolls_only_as_far_as_needed() {
+        let edge = page(ScrollPolicy::Edge, 0.);
+        // Below the view, the band comes up to just inside the bottom edge
+        assert_eq!(scroll_to(&edge, 0., BAND, &VIEW), 2300. + EDGE_MARGIN - 1000.);
+        // Already in view, nothing moves
+        assert_eq!(scroll_to(&edge, 1500., BAND, &VIEW), 1500.);
+        // Above the view, it comes down to just inside the top edge
+        assert_eq!(scroll_to(&edge, 2500., BAND, &VIEW), 2000. - EDGE_MARGIN);
+    }
+
+    #[test]
+    fn peek_keeps_some_of_the_next_line_in_view() {
+        assert_eq!(
+            scroll_to(&page(ScrollPolicy::Edge, 120.), 0., BAND, &VIEW),
+            2300. + 120. + EDGE_MARGIN - 1000.
+        );
+        assert_eq!(
+            scroll_to(&page(ScrollPolicy::Centered, 500.), 0., BAND, &VIEW),
+            2300. + 500. - 1000.
+        );
+        // A peek that already fits changes nothing
+        assert_eq!(scroll_to(&page(ScrollPolicy::Centered, 200.), 0., BAND, &VIEW), 1650.);
+    }
+
+    #[test]
+    fn scrolling_is_clamped_at_both_ends() {
+        // A peek too big to fit stops with the focused line at the top of the view rather than above it
+        assert_eq!(scroll_to(&page(ScrollPolicy::Centered, 900.), 0., BAND, &VIEW), 2000.);
+        // Nothing scrolls above the start of the page
+        assert_eq!(scroll_to(&page(ScrollPolicy::Centered, 0.), 0., (0., 300.), &VIEW), 0.);
+        assert_eq!(scroll_to(&page(ScrollPolicy::Edge, 0.), 500., (0., 300.), &VIEW), 0.);
+    }
+}
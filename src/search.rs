@@ -0,0 +1,428 @@
+use sfml::window::mouse::Button;
+use sfml::window::Event;
+
+use crate::detail::Detail;
+use crate::focus::{self, Direction, FocusId, Focusable};
+use crate::input::Action;
+use crate::layout::{self, Rect};
+use crate::screen::{Screen, ScreenKind, Transition};
+use crate::scroll::{self, View};
+use crate::{app_gl, fuzzy, skeleton, util, App};
+
+static QUERY_CHAR_SIZE: isize = 56 * 32;
+static KEY_CHAR_SIZE: isize = 32 * 32;
+static MAX_QUERY_CHARS: usize = 40;
+static MAX_RESULTS: usize = 30;
+
+static KEY_SIZE: f32 = 90.;
+static KEY_GAP: f32 = 12.;
+static KEYBOARD_ORIGIN: (f32, f32) = (100., 240.);
+static RESULT_SIZE: (f32, f32) = (320., 180.);
+static RESULT_GAP: f32 = 30.;
+static RESULT_COLUMNS: usize = 3;
+static RESULTS_ORIGIN: (f32, f32) = (820., 240.);
+// How quickly the results grid catches up with its scroll target
+static RESULTS_SCROLL_RATE: f32 = 12.;
+
+static KEYBOARD_ROWS: [&str; 6] = ["ABCDEF", "GHIJKL", "MNOPQR", "STUVWX", "YZ1234", "567890"];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum KeyCap {
+    Char(char),
+    Space,
+    Delete,
+    Clear,
+}
+
+#[derive(Debug)]
+struct KeyboardKey {
+    cap: KeyCap,
+    column: usize,
+    row: usize,
+    span: usize,
+    label: app_gl::RenderedImage,
+}
+
+fn build_keyboard() -> Vec<KeyboardKey> {
+    let mut keys = Vec::new();
+    for (row, letters) in KEYBOARD_ROWS.iter().enumerate() {
+        for (column, c) in letters.chars().enumerate() {
+            keys.push(KeyboardKey {
+                cap: KeyCap::Char(c),
+                column,
+                row,
+                span: 1,
+                label: app_gl::render_sized_text_to_texture(&c.to_string(), KEY_CHAR_SIZE),
+            });
+        }
+    }
+
+    let specials = [(KeyCap::Space, "Space"), (KeyCap::Delete, "Delete"), (KeyCap::Clear, "Clear")];
+    for (idx, (cap, name)) in specials.iter().enumerate() {
+        keys.push(KeyboardKey {
+            cap: *cap,
+            column: idx * 2,
+            row: KEYBOARD_ROWS.len(),
+            span: 2,
+            label: app_gl::render_sized_text_to_texture(name, KEY_CHAR_SIZE),
+        });
+    }
+    keys
+}
+
+fn key_rect(key: &KeyboardKey, window_size: &(u32, u32)) -> Rect {
+    let width = key.span as f32 * KEY_SIZE + (key.span - 1) as f32 * KEY_GAP;
+    let left = KEYBOARD_ORIGIN.0 + key.column as f32 * (KEY_SIZE + KEY_GAP);
+    let top = window_size.1 as f32 - KEYBOARD_ORIGIN.1 - key.row as f32 * (KEY_SIZE + KEY_GAP);
+    Rect {
+        x: left + width / 2.,
+        y: top - KEY_SIZE / 2.,
+        width,
+        height: KEY_SIZE,
+    }
+}
+
+// Distance from the top of the results grid to the top of the result's line
+fn result_top(result_idx: usize) -> f32 {
+    (result_idx / RESULT_COLUMNS) as f32 * (RESULT_SIZE.1 + RESULT_GAP)
+}
+
+fn result_rect(result_idx: usize, scroll: f32, window_size: &(u32, u32)) -> Rect {
+    let column = result_idx % RESULT_COLUMNS;
+    Rect {
+        x: RESULTS_ORIGIN.0 + column as f32 * (RESULT_SIZE.0 + RESULT_GAP) + RESULT_SIZE.0 / 2.,
+        y: window_size.1 as f32 - RESULTS_ORIGIN.1 - result_top(result_idx) + scroll - RESULT_SIZE.1 / 2.,
+        width: RESULT_SIZE.0,
+        height: RESULT_SIZE.1,
+    }
+}
+
+fn loaded_tile_count(app: &App) -> usize {
+    app.containers.iter().map(|c| c.images.len()).sum()
+}
+
+// Best matches for the query among every loaded item, as (container, tile) indices
+fn find_matches(app: &App, query: &str) -> Vec<(usize, usize)> {
+    let mut scored: Vec<(i32, usize, usize)> = Vec::new();
+    let mut seen_titles: Vec<&str> = Vec::new();
+    for (c_idx, container) in app.containers.iter().enumerate() {
+        for (t_idx, tile) in container.images.iter().enumerate() {
+            // The same title often appears in several rows
+            if seen_titles.contains(&tile.item.title.as_str()) {
+                continue;
+            }
+            if let Some(score) = fuzzy::score(query, &tile.item.title) {
+                seen_titles.push(&tile.item.title);
+                scored.push((score, c_idx, t_idx));
+            }
+        }
+    }
+
+    scored.sort_by(|a, b| {
+        let a_len = app.containers[a.1].images[a.2].item.title.len();
+        let b_len = app.containers[b.1].images[b.2].item.title.len();
+        b.0.cmp(&a.0).then(a_len.cmp(&b_len))
+    });
+    scored.iter().take(MAX_RESULTS).map(|e| (e.1, e.2)).collect()
+}
+
+// Query entry with an on-screen keyboard and a grid of matching titles
+pub struct Search {
+    query: String,
+    query_image: app_gl::RenderedImage,
+    placeholder: app_gl::RenderedImage,
+    no_results: app_gl::RenderedImage,
+    keys: Vec<KeyboardKey>,
+    results: Vec<(usize, usize)>,
+    // Tiles loaded when the results were last computed, so late arrivals get searched too
+    searched_tile_count: usize,
+    focused: FocusId,
+    results_scroll: f32,
+    results_scroll_target: f32,
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        app_gl::release_texture(self.query_image.texture_id);
+        app_gl::release_texture(self.placeholder.texture_id);
+        app_gl::release_texture(self.no_results.texture_id);
+        for key in &self.keys {
+            app_gl::release_texture(key.label.texture_id);
+        }
+    }
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Search {
+            query: String::new(),
+            query_image: app_gl::render_sized_text_to_texture("", QUERY_CHAR_SIZE),
+            placeholder: app_gl::render_sized_text_to_texture("Search titles", QUERY_CHAR_SIZE),
+            no_results: app_gl::render_sized_text_to_texture("No matching titles", KEY_CHAR_SIZE),
+            keys: build_keyboard(),
+            results: Vec::new(),
+            searched_tile_count: 0,
+            focused: FocusId::Button(0),
+            results_scroll: 0.,
+            results_scroll_target: 0.,
+        }
+    }
+}
+
+impl Search {
+    fn set_query(&mut self, app: &App, query: String) {
+        if query == self.query {
+            return;
+        }
+
+        self.query = query;
+        let old = std::mem::replace(
+            &mut self.query_image,
+            app_gl::render_sized_text_to_texture(&self.query, QUERY_CHAR_SIZE),
+        );
+        app_gl::release_texture(old.texture_id);
+        self.results_scroll_target = 0.;
+        self.refresh_results(app);
+    }
+
+    fn refresh_results(&mut self, app: &App) {
+        self.results = find_matches(app, &self.query);
+        self.searched_tile_count = loaded_tile_count(app);
+
+        // Keep focus on the grid only while the focused result is still listed
+        if let Some(result) = self.focused_result() {
+            if !self.results.contains(&result) {
+                self.focused = FocusId::Button(0);
+            }
+        }
+    }
+
+    fn press_key(&mut self, app: &App, cap: KeyCap) {
+        let mut query = self.query.clone();
+        match cap {
+            KeyCap::Char(c) => query.push(c),
+            KeyCap::Space => query.push(' '),
+            KeyCap::Delete => {
+                query.pop();
+            }
+            KeyCap::Clear => query.clear(),
+        }
+        if query.chars().count() <= MAX_QUERY_CHARS {
+            self.set_query(app, query);
+        }
+    }
+
+    fn focused_result(&self) -> Option<(usize, usize)> {
+        match self.focused {
+            FocusId::Tile(c_idx, t_idx) => Some((c_idx, t_idx)),
+            _ => None,
+        }
+    }
+
+    fn focusables(&self, scroll: f32, window_size: &(u32, u32)) -> Vec<Focusable> {
+        let keys = self.keys.iter().enumerate().map(|(idx, key)| Focusable {
+            id: FocusId::Button(idx),
+            rect: key_rect(key, window_size),
+        });
+        let results = self.results.iter().enumerate().map(|(idx, (c_idx, t_idx))| Focusable {
+            id: FocusId::Tile(*c_idx, *t_idx),
+            rect: result_rect(idx, scroll, window_size),
+        });
+        keys.chain(results).collect()
+    }
+
+    // Focus decisions use where the grid is scrolling to rather than where it is mid-scroll
+    fn focused_rect(&self, window_size: &(u32, u32)) -> Rect {
+        let focusables = self.focusables(self.results_scroll_target, window_size);
+        match focusables.iter().find(|e| e.id == self.focused) {
+            Some(focusable) => focusable.rect,
+            None => key_rect(&self.keys[0], window_size),
+        }
+    }
+
+    fn move_focus(&mut self, app: &App, direction: Direction) {
+        let window_size = &app.window_size;
+        let from = self.focused_rect(window_size);
+        if let Some(id) = focus::find_next(&from, direction, &self.focusables(self.results_scroll_target, window_size)) {
+            self.focused = id;
+            self.scroll_to_focus(app);
+        }
+    }
+
+    // Scrolls the results grid so the focused result is shown as the search screen's scroll policy asks
+    fn scroll_to_focus(&mut self, app: &App) {
+        let result_idx = match self
+            .focused_result()
+            .and_then(|result| self.results.iter().position(|e| *e == result))
+        {
+            Some(result_idx) => result_idx,
+            None => return,
+        };
+        let view = View {
+            top: 0.,
+            height: app.window_size.1 as f32 - RESULTS_ORIGIN.1,
+        };
+        let top = result_top(result_idx);
+        let page = app.scroll.for_screen(ScreenKind::Search);
+        self.results_scroll_target = scroll::scroll_to(&page, self.results_scroll_target, (top, top + RESULT_SIZE.1), &view);
+    }
+
+    fn focusable_at(&self, x: f32, y: f32, window_size: &(u32, u32)) -> Option<FocusId> {
+        self.focusables(self.results_scroll, window_size)
+            .iter()
+            .find(|e| e.rect.contains(x, y))
+            .map(|e| e.id)
+    }
+}
+
+impl Screen for Search {
+    fn kind(&self) -> ScreenKind {
+        ScreenKind::Search
+    }
+
+    fn captures_text(&self) -> bool {
+        true
+    }
+
+    fn handle_text(&mut self, app: &mut App, unicode: char) {
+        // Backspace arrives as a control character
+        if unicode == '\u{8}' {
+            self.press_key(app, KeyCap::Delete);
+        } else if !unicode.is_control() {
+            self.press_key(app, KeyCap::Char(unicode));
+        }
+    }
+
+    fn update(&mut self, app: &mut App, dt: f32) {
+        if !self.query.is_empty() && loaded_tile_count(app) != self.searched_tile_count {
+            self.refresh_results(app);
+        }
+
+        let catch_up = 1. - (-RESULTS_SCROLL_RATE * dt).exp();
+        self.results_scroll += (self.results_scroll_target - self.results_scroll) * catch_up;
+    }
+
+    fn handle_action(&mut self, app: &mut App, action: Action) -> Transition {
+        match action {
+            Action::Back | Action::Search => return Transition::Pop,
+            Action::Up => self.move_focus(app, Direction::Up),
+            Action::Down => self.move_focus(app, Direction::Down),
+            Action::Left => self.move_focus(app, Direction::Left),
+            Action::Right => self.move_focus(app, Direction::Right),
+            Action::Select => match self.focused {
+                FocusId::Button(idx) => {
+                    let cap = self.keys[idx].cap;
+                    self.press_key(app, cap);
+                }
+                FocusId::Tile(c_idx, t_idx) => {
+                    let result_idx = self.results.iter().position(|e| *e == (c_idx, t_idx)).unwrap_or(0);
+                    let from_rect = result_rect(result_idx, self.results_scroll, &app.window_size);
+                    if let Some(detail) = Detail::open_from(app, c_idx, t_idx, from_rect) {
+                        return Transition::Push(Box::new(detail));
+                    }
+                }
+            },
+            _ => {}
+        }
+        Transition::None
+    }
+
+    fn map_mouse_event(&mut self, _app: &mut App, event: &Event, window_size: &(u32, u32)) -> Option<Action> {
+        match *event {
+            Event::MouseMoved { x, y } => {
+                let (x, y) = layout::from_window_coords(x, y, window_size);
+                if let Some(id) = self.focusable_at(x, y, window_size) {
+                    self.focused = id;
+                }
+                None
+            }
+            Event::MouseButtonReleased {
+                button: Button::LEFT,
+                x,
+                y,
+            } => {
+                let (x, y) = layout::from_window_coords(x, y, window_size);
+                self.focused = self.focusable_at(x, y, window_size)?;
+                Some(Action::Select)
+            }
+            _ => None,
+        }
+    }
+
+    fn render(&self, app: &App, progress: f32, window_size: &(u32, u32)) {
+        app_gl::draw_color_rect(
+            app,
+            &app_gl::screen_rect(window_size),
+            [0.03, 0.04, 0.06, 0.95 * progress],
+            window_size,
+        );
+        let alpha = util::clamp(progress * 2. - 1., 0., 1.);
+
+        let (query_image, query_alpha) = if self.query.is_empty() {
+            (&self.placeholder, 0.4)
+        } else {
+            (&self.query_image, 1.)
+        };
+        let query_rect = Rect {
+            x: KEYBOARD_ORIGIN.0 + query_image.width as f32 / 2.,
+            y: window_size.1 as f32 - 120.,
+            width: query_image.width as f32,
+            height: query_image.height as f32,
+        };
+        app_gl::draw_text(app, &query_rect, query_image.texture_id, alpha * query_alpha, window_size);
+
+        for (idx, key) in self.keys.iter().enumerate() {
+            let rect = key_rect(key, window_size);
+            let color = if self.focused == FocusId::Button(idx) {
+                [0.2, 0.45, 0.9, alpha]
+            } else {
+                [1., 1., 1., 0.1 * alpha]
+            };
+            app_gl::draw_color_rect(app, &rect, color, window_size);
+
+            let label_rect = Rect {
+                width: key.label.width as f32,
+                height: key.label.height as f32,
+                ..rect
+            };
+            app_gl::draw_text(app, &label_rect, key.label.texture_id, alpha, window_size);
+        }
+
+        for (idx, (c_idx, t_idx)) in self.results.iter().enumerate() {
+            let tile = match app.containers.get(*c_idx).and_then(|c| c.images.get(*t_idx)) {
+                Some(tile) => tile,
+                None => continue,
+            };
+
+            let rect = result_rect(idx, self.results_scroll, window_size);
+            // Results scrolled up past the top of the grid fade out rather than covering the query
+            let overflow = rect.y + rect.height / 2. - (window_size.1 as f32 - RESULTS_ORIGIN.1);
+            let alpha = alpha * util::clamp(1. - overflow / RESULT_SIZE.1, 0., 1.);
+            if alpha <= 0. {
+                continue;
+            }
+            if self.focused == FocusId::Tile(*c_idx, *t_idx) {
+                let outline = Rect {
+                    width: rect.width + 12.,
+                    height: rect.height + 12.,
+                    ..rect
+                };
+                app_gl::draw_color_rect(app, &outline, [1., 1., 1., alpha], window_size);
+            }
+            match tile.texture().or(tile.preview_texture_id) {
+                Some(texture_id) => app_gl::draw_image(app, &rect, texture_id, alpha, window_size),
+                None => skeleton::draw(app, &rect, alpha, window_size),
+            }
+        }
+
+        if !self.query.is_empty() && self.results.is_empty() {
+            let rect = Rect {
+                x: RESULTS_ORIGIN.0 + self.no_results.width as f32 / 2.,
+                y: window_size.1 as f32 - RESULTS_ORIGIN.1 - self.no_results.height as f32 / 2.,
+                width: self.no_results.width as f32,
+                height: self.no_results.height as f32,
+            };
+            app_gl::draw_text(app, &rect, self.no_results.texture_id, alpha * 0.6, window_size);
+        }
+    }
+}
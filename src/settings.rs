@@ -0,0 +1,251 @@
+use sfml::window::mouse::Button;
+use sfml::window::Event;
+
+use crate::focus::FocusMode;
+use crate::input::Action;
+use crate::layout::{self, Rect};
+use crate::motion::MotionStyle;
+use crate::screen::{Screen, ScreenKind, Transition};
+use crate::scroll::ScrollPolicy;
+use crate::sound::SoundEvent;
+use crate::spring::SpringParams;
+use crate::{app_gl, music, util, App};
+
+static HEADING_CHAR_SIZE: isize = 56 * 32;
+static OPTION_CHAR_SIZE: isize = 36 * 32;
+static PANEL_WIDTH: f32 = 760.;
+static OPTION_HEIGHT: f32 = 90.;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Setting {
+    FocusMemory,
+    RowMotion,
+    PageScroll,
+    Sounds,
+    MusicVolume,
+}
+
+static SETTINGS: [Setting; 5] = [
+    Setting::FocusMemory,
+    Setting::RowMotion,
+    Setting::PageScroll,
+    Setting::Sounds,
+    Setting::MusicVolume,
+];
+// Steps the music volume cycles through
+static MUSIC_VOLUMES: [f32; 6] = [0., 0.2, 0.4, 0.6, 0.8, 1.];
+// Order the home page scroll policy cycles through
+static SCROLL_POLICIES: [ScrollPolicy; 4] = [
+    ScrollPolicy::Top,
+    ScrollPolicy::Centered,
+    ScrollPolicy::Anchor(0.3),
+    ScrollPolicy::Edge,
+];
+
+fn label(app: &App, setting: Setting) -> String {
+    match setting {
+        Setting::FocusMemory => match app.focus.mode {
+            FocusMode::RememberPerRow => "Row focus: Remember per row".to_string(),
+            FocusMode::NearestColumn => "Row focus: Nearest column".to_string(),
+        },
+        Setting::RowMotion => match app.motion.row_scroll {
+            MotionStyle::Spring(_) => "Row scrolling: Spring".to_string(),
+            MotionStyle::Tween => "Row scrolling: Tween".to_string(),
+        },
+        Setting::PageScroll => format!("Page scrolling: {}", app.scroll.for_screen(ScreenKind::Home).policy.name()),
+        Setting::Sounds => {
+            if app.sounds.muted {
+                "Sounds: Muted".to_string()
+            } else {
+                "Sounds: On".to_string()
+            }
+        }
+        Setting::MusicVolume => format!("Music volume: {}%", (app.music_settings.volume * 100.).round()),
+    }
+}
+
+fn toggle(app: &mut App, setting: Setting) {
+    match setting {
+        Setting::FocusMemory => {
+            app.focus.mode = match app.focus.mode {
+                FocusMode::RememberPerRow => FocusMode::NearestColumn,
+                FocusMode::NearestColumn => FocusMode::RememberPerRow,
+            };
+        }
+        Setting::RowMotion => {
+            app.motion.row_scroll = match app.motion.row_scroll {
+                MotionStyle::Spring(_) => MotionStyle::Tween,
+                MotionStyle::Tween => MotionStyle::Spring(SpringParams::critically_damped(200.)),
+            };
+        }
+        Setting::PageScroll => {
+            let mut page = app.scroll.for_screen(ScreenKind::Home);
+            let current = SCROLL_POLICIES.iter().position(|p| p.name() == page.policy.name()).unwrap_or(0);
+            page.policy = SCROLL_POLICIES[(current + 1) % SCROLL_POLICIES.len()];
+            app.scroll.set(ScreenKind::Home, page);
+        }
+        Setting::Sounds => app.sounds.toggle_mute(),
+        Setting::MusicVolume => {
+            let current = MUSIC_VOLUMES
+                .iter()
+                .position(|v| *v >= app.music_settings.volume - 0.01)
+                .unwrap_or(0);
+            app.music_settings.volume = MUSIC_VOLUMES[(current + 1) % MUSIC_VOLUMES.len()];
+            if let Err(err) = app.music_settings.save_user_settings(music::USER_MUSIC_SETTINGS_PATH) {
+                println!("{}", err);
+            }
+        }
+    }
+}
+
+// Side panel listing runtime options; Select, Left or Right cycles the focused option
+pub struct Settings {
+    heading: app_gl::RenderedImage,
+    options: Vec<app_gl::RenderedImage>,
+    focused: usize,
+}
+
+impl Drop for Settings {
+    fn drop(&mut self) {
+        app_gl::release_texture(self.heading.texture_id);
+        for option in &self.options {
+            app_gl::release_texture(option.texture_id);
+        }
+    }
+}
+
+// Panel slides in from the right edge as the screen enters
+fn panel_rect(progress: f32, window_size: &(u32, u32)) -> Rect {
+    let t = 1. - (1. - progress).powi(3);
+    Rect {
+        x: window_size.0 as f32 + PANEL_WIDTH / 2. - PANEL_WIDTH * t,
+        y: window_size.1 as f32 / 2.,
+        width: PANEL_WIDTH,
+        height: window_size.1 as f32,
+    }
+}
+
+fn option_rect(option_idx: usize, progress: f32, window_size: &(u32, u32)) -> Rect {
+    let panel = panel_rect(progress, window_size);
+    Rect {
+        x: panel.x,
+        y: window_size.1 as f32 - 260. - (OPTION_HEIGHT + 20.) * option_idx as f32,
+        width: PANEL_WIDTH - 80.,
+        height: OPTION_HEIGHT,
+    }
+}
+
+impl Settings {
+    pub fn new(app: &App) -> Self {
+        Settings {
+            heading: app_gl::render_sized_text_to_texture("Settings", HEADING_CHAR_SIZE),
+            options: SETTINGS
+                .iter()
+                .map(|setting| app_gl::render_sized_text_to_texture(&label(app, *setting), OPTION_CHAR_SIZE))
+                .collect(),
+            focused: 0,
+        }
+    }
+
+    fn toggle_focused(&mut self, app: &mut App) {
+        let setting = SETTINGS[self.focused];
+        toggle(app, setting);
+        let old = std::mem::replace(
+            &mut self.options[self.focused],
+            app_gl::render_sized_text_to_texture(&label(app, setting), OPTION_CHAR_SIZE),
+        );
+        app_gl::release_texture(old.texture_id);
+    }
+
+    fn move_focus(&mut self, app: &mut App, delta: i32) {
+        let focused = util::clamp(self.focused as i32 + delta, 0, self.options.len() as i32 - 1) as usize;
+        app.sounds.play_move(focused != self.focused);
+        self.focused = focused;
+    }
+
+    fn option_at(&self, x: f32, y: f32, window_size: &(u32, u32)) -> Option<usize> {
+        (0..self.options.len()).find(|idx| option_rect(*idx, 1., window_size).contains(x, y))
+    }
+}
+
+impl Screen for Settings {
+    fn kind(&self) -> ScreenKind {
+        ScreenKind::Settings
+    }
+
+    fn handle_action(&mut self, app: &mut App, action: Action) -> Transition {
+        match action {
+            Action::Back | Action::Settings => return Transition::Pop,
+            Action::Up => self.move_focus(app, -1),
+            Action::Down => self.move_focus(app, 1),
+            Action::Select | Action::Left | Action::Right => {
+                self.toggle_focused(app);
+                app.sounds.play(SoundEvent::Select);
+            }
+            _ => {}
+        }
+        Transition::None
+    }
+
+    fn map_mouse_event(&mut self, _app: &mut App, event: &Event, window_size: &(u32, u32)) -> Option<Action> {
+        match *event {
+            Event::MouseMoved { x, y } => {
+                let (x, y) = layout::from_window_coords(x, y, window_size);
+                if let Some(idx) = self.option_at(x, y, window_size) {
+                    self.focused = idx;
+                }
+                None
+            }
+            Event::MouseButtonReleased {
+                button: Button::LEFT,
+                x,
+                y,
+            } => {
+                let (x, y) = layout::from_window_coords(x, y, window_size);
+                // Clicking outside the panel closes it
+                if !panel_rect(1., window_size).contains(x, y) {
+                    return Some(Action::Back);
+                }
+                self.focused = self.option_at(x, y, window_size)?;
+                Some(Action::Select)
+            }
+            _ => None,
+        }
+    }
+
+    fn render(&self, app: &App, progress: f32, window_size: &(u32, u32)) {
+        app_gl::draw_color_rect(app, &app_gl::screen_rect(window_size), [0., 0., 0., 0.5 * progress], window_size);
+        let panel = panel_rect(progress, window_size);
+        app_gl::draw_color_rect(app, &panel, [0.08, 0.09, 0.12, 0.95], window_size);
+
+        let heading_rect = Rect {
+            x: panel.x - PANEL_WIDTH / 2. + 40. + self.heading.width as f32 / 2.,
+            y: window_size.1 as f32 - 120.,
+            width: self.heading.width as f32,
+            height: self.heading.height as f32,
+        };
+        app_gl::draw_text(app, &heading_rect, self.heading.texture_id, 1., window_size);
+
+        for (option_idx, option) in self.options.iter().enumerate() {
+            let rect = option_rect(option_idx, progress, window_size);
+            if option_idx == self.focused {
+                app_gl::draw_color_rect(app, &rect, [0.2, 0.45, 0.9, 1.], window_size);
+            }
+
+            let text_rect = Rect {
+                x: rect.x - rect.width / 2. + 30. + option.width as f32 / 2.,
+                width: option.width as f32,
+                height: option.height as f32,
+                ..rect
+            };
+            let alpha = if option_idx == self.focused { 1. } else { 0.7 };
+            app_gl::draw_text(
+                app,
+                &text_rect,
+                option.texture_id,
+                util::clamp(alpha * progress * 2., 0., alpha),
+                window_size,
+            );
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::layout::Rect;
+use crate::{app_gl, App};
+
+// Placeholder tiles shown in a row that is still waiting to hear which items it holds
+pub static ROW_SKELETON_TILES: usize = 6;
+// Shimmer cycles per second, and how far apart in pixels neighbouring phases of the wave sit
+static SHIMMER_SPEED: f32 = 1.2;
+static SHIMMER_WAVELENGTH: f32 = 1400.;
+static BASE_SHADE: f32 = 0.14;
+static SHIMMER_SHADE: f32 = 0.07;
+static FAILED_COLOR: [f32; 4] = [0.1, 0.1, 0.12, 1.];
+static FOCUS_OUTLINE: f32 = 8.;
+
+pub fn tick(app: &mut App, dt: f32) {
+    app.skeleton_time += dt;
+}
+
+// Grey block whose shade sweeps across the screen from left to right
+pub fn draw(app: &App, rect: &Rect, alpha: f32, window_size: &(u32, u32)) {
+    if alpha <= 0. {
+        return;
+    }
+
+    let phase = (app.skeleton_time * SHIMMER_SPEED - rect.x / SHIMMER_WAVELENGTH) * std::f32::consts::PI * 2.;
+    let shade = BASE_SHADE + SHIMMER_SHADE * (0.5 + 0.5 * phase.cos());
+    app_gl::draw_color_rect(app, rect, [shade, shade, shade * 1.1, alpha], window_size);
+}
+
+// Flat block left in place of artwork that could not be loaded
+pub fn draw_failed(app: &App, rect: &Rect, alpha: f32, window_size: &(u32, u32)) {
+    let [r, g, b, a] = FAILED_COLOR;
+    app_gl::draw_color_rect(app, rect, [r, g, b, a * alpha], window_size);
+}
+
+// Outline marking focus on a tile without artwork, standing in for the tile shader's border
+pub fn draw_focus_outline(app: &App, rect: &Rect, alpha: f32, window_size: &(u32, u32)) {
+    let outline = Rect {
+        width: rect.width + FOCUS_OUTLINE,
+        height: rect.height + FOCUS_OUTLINE,
+        ..*rect
+    };
+    app_gl::draw_color_rect(app, &outline, [1., 1., 1., alpha], window_size);
+}
@@ -0,0 +1,287 @@
+use sfml::audio::{Sound, SoundBuffer, SoundSource, SoundStatus};
+use sfml::SfBox;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::rc::Rc;
+
+use crate::util;
+
+static SAMPLE_RATE: u32 = 44_100;
+
+// Interface events that make a sound
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SoundEvent {
+    FocusMove,
+    Select,
+    Back,
+    // Navigation that ran into the edge of the page or a row
+    Bump,
+}
+
+static SOUND_EVENTS: [(&str, SoundEvent); 4] = [
+    ("FocusMove", SoundEvent::FocusMove),
+    ("Select", SoundEvent::Select),
+    ("Back", SoundEvent::Back),
+    ("Bump", SoundEvent::Bump),
+];
+
+// Where sounds go, swappable so the interface can run without an audio device
+pub trait SoundBackend {
+    // `volume` runs from 0 to 1
+    fn play(&mut self, event: SoundEvent, volume: f32);
+    fn is_playing(&self) -> bool;
+}
+
+// Frequency, length in seconds and default volume of the tone played when an event has no sound file
+fn default_tone(event: SoundEvent) -> (f32, f32, f32) {
+    match event {
+        SoundEvent::FocusMove => (880., 0.04, 0.3),
+        SoundEvent::Select => (660., 0.08, 0.5),
+        SoundEvent::Back => (440., 0.08, 0.5),
+        SoundEvent::Bump => (160., 0.1, 0.5),
+    }
+}
+
+// Short sine blip that dies away, so the interface has sounds without shipping any files
+fn synthesize_tone(frequency: f32, length: f32) -> Option<SfBox<SoundBuffer>> {
+    let sample_count = (SAMPLE_RATE as f32 * length) as usize;
+    let samples: Vec<i16> = (0..sample_count)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let envelope = 1. - i as f32 / sample_count as f32;
+            ((t * frequency * std::f32::consts::PI * 2.).sin() * envelope * envelope * i16::MAX as f32) as i16
+        })
+        .collect();
+    SoundBuffer::from_samples(&samples, 1, SAMPLE_RATE)
+}
+
+// Plays through SFML's audio module with every buffer loaded up front
+pub struct SfmlSounds {
+    sounds: Vec<(SoundEvent, Sound<'static>)>,
+}
+
+impl SfmlSounds {
+    pub fn load(settings: &SoundSettings) -> Self {
+        let mut sounds = Vec::new();
+        for (_, event) in SOUND_EVENTS.iter() {
+            let buffer = match settings.file_for(*event) {
+                Some(path) => SoundBuffer::from_file(path).or_else(|| {
+                    println!("Failed to load sound {}, using a default tone", path);
+                    let (frequency, length, _) = default_tone(*event);
+                    synthesize_tone(frequency, length)
+                }),
+                None => {
+                    let (frequency, length, _) = default_tone(*event);
+                    synthesize_tone(frequency, length)
+                }
+            };
+            // Buffers live for the rest of the run, which lets every sound keep a plain reference to its own
+            if let Some(buffer) = buffer {
+                let buffer: &'static SfBox<SoundBuffer> = Box::leak(Box::new(buffer));
+                sounds.push((*event, Sound::with_buffer(buffer)));
+            }
+        }
+        SfmlSounds { sounds }
+    }
+}
+
+impl SoundBackend for SfmlSounds {
+    fn play(&mut self, event: SoundEvent, volume: f32) {
+        if let Some((_, sound)) = self.sounds.iter_mut().find(|(e, _)| *e == event) {
+            sound.set_volume(volume * 100.);
+            sound.play();
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        self.sounds.iter().any(|(_, sound)| sound.status() == SoundStatus::PLAYING)
+    }
+}
+
+// Backend that plays nothing and remembers what it was asked to play; clones share the same record
+#[derive(Clone, Default)]
+pub struct RecordingSounds {
+    played: Rc<RefCell<Vec<(SoundEvent, f32)>>>,
+}
+
+impl RecordingSounds {
+    pub fn played(&self) -> Vec<SoundEvent> {
+        self.played.borrow().iter().map(|(event, _)| *event).collect()
+    }
+}
+
+impl SoundBackend for RecordingSounds {
+    fn play(&mut self, event: SoundEvent, volume: f32) {
+        self.played.borrow_mut().push((event, volume));
+    }
+
+    fn is_playing(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug)]
+pub struct SoundSettings {
+    files: Vec<(SoundEvent, String)>,
+    volumes: Vec<(SoundEvent, f32)>,
+    muted: bool,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings {
+            files: Vec::new(),
+            volumes: SOUND_EVENTS.iter().map(|(_, event)| (*event, default_tone(*event).2)).collect(),
+            muted: false,
+        }
+    }
+}
+
+impl SoundSettings {
+    // Loads settings of the form { "muted": false, "Select": { "file": "res/sounds/select.wav", "volume": 0.5 }, ... } on top of the defaults
+    pub fn load_from_disk(path: &str) -> Result<SoundSettings, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open sound settings file {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read sound settings file {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed sound settings file {}: {:?}", path, err))?;
+        let entries = data
+            .as_object()
+            .ok_or(format!("Sound settings file {} should be an object", path))?;
+
+        let mut settings = SoundSettings::default();
+        for (name, entry) in entries {
+            if name == "muted" {
+                settings.muted = entry.as_bool().ok_or("Sound setting muted should be true or false".to_string())?;
+                continue;
+            }
+
+            let event = SOUND_EVENTS
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, e)| *e)
+                .ok_or(format!("Unknown sound event: {:?}", name))?;
+            if let Some(file) = entry["file"].as_str() {
+                settings.files.push((event, file.to_string()));
+            }
+            if let Some(volume) = entry["volume"].as_f64() {
+                settings.volumes.retain(|(e, _)| *e != event);
+                settings.volumes.push((event, util::clamp(volume as f32, 0., 1.)));
+            }
+        }
+
+        Ok(settings)
+    }
+
+    fn file_for(&self, event: SoundEvent) -> Option<&str> {
+        self.files.iter().find(|(e, _)| *e == event).map(|(_, path)| path.as_str())
+    }
+}
+
+pub struct SoundManager {
+    backend: Box<dyn SoundBackend>,
+    // Shares the backend's record when sounds are being recorded rather than played
+    recording: Option<RecordingSounds>,
+    volumes: Vec<(SoundEvent, f32)>,
+    pub muted: bool,
+}
+
+impl SoundManager {
+    pub fn new(backend: Box<dyn SoundBackend>, settings: &SoundSettings) -> Self {
+        SoundManager {
+            backend,
+            recording: None,
+            volumes: settings.volumes.clone(),
+            muted: settings.muted,
+        }
+    }
+
+    // Records sounds instead of playing them, never touching the audio device
+    pub fn recording(settings: &SoundSettings) -> Self {
+        let recording = RecordingSounds::default();
+        SoundManager {
+            recording: Some(recording.clone()),
+            ..SoundManager::new(Box::new(recording), settings)
+        }
+    }
+
+    // Sounds played so far, when recording
+    pub fn recorded(&self) -> Option<Vec<SoundEvent>> {
+        self.recording.as_ref().map(|recording| recording.played())
+    }
+
+    pub fn play(&mut self, event: SoundEvent) {
+        if self.muted {
+            return;
+        }
+        let volume = self.volumes.iter().find(|(e, _)| *e == event).map(|(_, v)| *v).unwrap_or(1.);
+        self.backend.play(event, volume);
+    }
+
+    // Whether a sound is still audible, so music can duck under it
+    pub fn is_playing(&self) -> bool {
+        self.backend.is_playing()
+    }
+
+    // Moving focus sounds a step, or a bump when focus had nowhere to go
+    pub fn play_move(&mut self, moved: bool) {
+        self.play(if moved { SoundEvent::FocusMove } else { SoundEvent::Bump });
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_sounds_at_their_volumes() {
+        let mut sounds = SoundManager::recording(&SoundSettings::default());
+        sounds.play(SoundEvent::Select);
+        sounds.play(SoundEvent::Back);
+        assert_eq!(sounds.recorded(), Some(vec![SoundEvent::Select, SoundEvent::Back]));
+        let volumes: Vec<f32> = sounds.recording.as_ref().unwrap().played.borrow().iter().map(|(_, v)| *v).collect();
+        assert_eq!(volumes, vec![default_tone(SoundEvent::Select).2, default_tone(SoundEvent::Back).2]);
+    }
+
+    #[test]
+    fn muting_silences_everything_until_unmuted() {
+        let mut sounds = SoundManager::recording(&SoundSettings::default());
+        sounds.toggle_mute();
+        sounds.play(SoundEvent::Select);
+        sounds.play_move(false);
+        assert_eq!(sounds.recorded(), Some(vec![]));
+        sounds.toggle_mute();
+        sounds.play_move(true);
+        assert_eq!(sounds.recorded(), Some(vec![SoundEvent::FocusMove]));
+    }
+
+    #[test]
+    fn settings_set_volume_and_mute() {
+        let path = std::env::temp_dir().join(format!("sfml_example_sounds_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{ "muted": true, "Bump": { "volume": 2 } }"#).unwrap();
+        let settings = SoundSettings::load_from_disk(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        let settings = settings.unwrap();
+
+        let mut sounds = SoundManager::recording(&settings);
+        assert!(sounds.muted);
+        sounds.toggle_mute();
+        sounds.play_move(false);
+        assert_eq!(*sounds.recording.as_ref().unwrap().played.borrow(), vec![(SoundEvent::Bump, 1.)]);
+    }
+
+    #[test]
+    fn unknown_events_are_rejected() {
+        let path = std::env::temp_dir().join(format!("sfml_example_bad_sounds_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{ "Boing": { "volume": 1 } }"#).unwrap();
+        let settings = SoundSettings::load_from_disk(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(settings.is_err());
+    }
+}
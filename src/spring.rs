@@ -0,0 +1,160 @@
+use crate::tween::{get_property, set_property, Property};
+use crate::App;
+
+// Largest step the integrator takes at once so stiff springs stay stable on long frames
+static MAX_SUBSTEP: f32 = 1. / 240.;
+static REST_DISTANCE: f32 = 0.001;
+static REST_VELOCITY: f32 = 0.01;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpringParams {
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl SpringParams {
+    // Damping that reaches the target as fast as possible without overshooting
+    pub fn critically_damped(stiffness: f32) -> Self {
+        SpringParams {
+            stiffness,
+            damping: 2. * stiffness.sqrt(),
+        }
+    }
+}
+
+impl Default for SpringParams {
+    fn default() -> Self {
+        SpringParams::critically_damped(150.)
+    }
+}
+
+#[derive(Debug)]
+struct Spring {
+    property: Property,
+    value: f32,
+    velocity: f32,
+    target: f32,
+    params: SpringParams,
+}
+
+impl Spring {
+    fn step(&mut self, dt: f32) {
+        let mut remaining = dt;
+        while remaining > 0. {
+            let h = remaining.min(MAX_SUBSTEP);
+            let accel = self.params.stiffness * (self.target - self.value) - self.params.damping * self.velocity;
+            self.velocity += accel * h;
+            self.value += self.velocity * h;
+            remaining -= h;
+        }
+    }
+
+    fn is_at_rest(&self) -> bool {
+        (self.target - self.value).abs() < REST_DISTANCE && self.velocity.abs() < REST_VELOCITY
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Springs {
+    active: Vec<Spring>,
+}
+
+impl Springs {
+    // Moves the target of the spring on `property`, keeping its velocity if one is already in motion
+    pub fn set_target(&mut self, property: Property, value: f32, target: f32, params: SpringParams) {
+        match self.active.iter_mut().find(|e| e.property == property) {
+            Some(spring) => {
+                spring.target = target;
+                spring.params = params;
+            }
+            None => {
+                self.active.push(Spring {
+                    property,
+                    value,
+                    velocity: 0.,
+                    target,
+                    params,
+                });
+            }
+        }
+    }
+
+    pub fn cancel(&mut self, property: Property) {
+        self.active.retain(|e| e.property != property);
+    }
+}
+
+pub fn spring_to(app: &mut App, property: Property, to: f32, params: SpringParams) {
+    let value = get_property(app, property);
+    app.springs.set_target(property, value, to, params);
+}
+
+pub fn tick(app: &mut App, dt: f32) {
+    let mut active = std::mem::take(&mut app.springs.active);
+    for spring in &mut active {
+        spring.step(dt);
+        if spring.is_at_rest() {
+            spring.value = spring.target;
+            spring.velocity = 0.;
+        }
+        set_property(app, spring.property, spring.value);
+    }
+
+    active.retain(|e| !(e.value == e.target && e.velocity == 0.));
+    app.springs.active = active;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spring(target: f32) -> Spring {
+        Spring {
+            property: Property::ViewportY,
+            value: 0.,
+            velocity: 0.,
+            target,
+            params: SpringParams::default(),
+        }
+    }
+
+    #[test]
+    fn retargeting_keeps_velocity() {
+        let mut springs = Springs::default();
+        springs.set_target(Property::ViewportY, 0., 100., SpringParams::default());
+        springs.active[0].step(0.1);
+        let (value, velocity) = (springs.active[0].value, springs.active[0].velocity);
+        assert!(velocity > 0.);
+
+        // The value passed in only seeds a new spring; one in motion carries on from where it is
+        springs.set_target(Property::ViewportY, 0., -100., SpringParams::default());
+        assert_eq!(springs.active.len(), 1);
+        assert_eq!(springs.active[0].value, value);
+        assert_eq!(springs.active[0].velocity, velocity);
+        assert_eq!(springs.active[0].target, -100.);
+    }
+
+    #[test]
+    fn critically_damped_spring_settles_without_overshoot() {
+        let mut spring = spring(100.);
+        for _ in 0..300 {
+            spring.step(1. / 60.);
+            assert!(spring.value <= 100. + REST_DISTANCE);
+        }
+        assert!(spring.is_at_rest());
+    }
+
+    #[test]
+    fn step_size_does_not_change_the_path() {
+        let mut coarse = spring(100.);
+        let mut fine = spring(100.);
+        for _ in 0..15 {
+            coarse.step(1. / 30.);
+        }
+        for _ in 0..120 {
+            fine.step(1. / 240.);
+        }
+        assert!((coarse.value - fine.value).abs() < 0.01);
+        assert!((coarse.velocity - fine.velocity).abs() < 0.1);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::tween::{set_property, Property};
+use crate::util;
+use crate::App;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Ease {
+    Linear,
+    OutCubic,
+}
+
+impl Ease {
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::OutCubic => 1. - (1. - t).powi(3),
+        }
+    }
+}
+
+// Declarative description of a group of animations laid out in time
+#[derive(Debug, Clone)]
+pub enum Timeline {
+    Tween {
+        property: Property,
+        from: f32,
+        to: f32,
+        duration: f32,
+        ease: Ease,
+    },
+    Delay(f32),
+    // Children run one after another
+    Sequence(Vec<Timeline>),
+    // Children all start together
+    Parallel(Vec<Timeline>),
+    // Children start `interval` seconds apart
+    Stagger {
+        interval: f32,
+        children: Vec<Timeline>,
+    },
+}
+
+impl Timeline {
+    pub fn tween(property: Property, from: f32, to: f32, duration: f32, ease: Ease) -> Self {
+        Timeline::Tween {
+            property,
+            from,
+            to,
+            duration,
+            ease,
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        match self {
+            Timeline::Tween { duration, .. } => *duration,
+            Timeline::Delay(duration) => *duration,
+            Timeline::Sequence(children) => children.iter().map(|e| e.duration()).sum(),
+            Timeline::Parallel(children) => children.iter().map(|e| e.duration()).fold(0., f32::max),
+            Timeline::Stagger { interval, children } => children
+                .iter()
+                .enumerate()
+                .map(|(idx, e)| idx as f32 * interval + e.duration())
+                .fold(0., f32::max),
+        }
+    }
+
+    // Collects the value of every tween that has started by local time `t`
+    fn apply(&self, values: &mut Vec<(Property, f32)>, t: f32) {
+        match self {
+            Timeline::Tween {
+                property,
+                from,
+                to,
+                duration,
+                ease,
+            } => {
+                if t < 0. {
+                    return;
+                }
+                let position = if *duration > 0. { util::clamp(t / duration, 0., 1.) } else { 1. };
+                let eased = ease.apply(position);
+                values.push((*property, (1. - eased) * from + eased * to));
+            }
+            Timeline::Delay(_) => {}
+            Timeline::Sequence(children) => {
+                let mut start = 0.;
+                for child in children {
+                    child.apply(values, t - start);
+                    start += child.duration();
+                }
+            }
+            Timeline::Parallel(children) => {
+                for child in children {
+                    child.apply(values, t);
+                }
+            }
+            Timeline::Stagger { interval, children } => {
+                for (idx, child) in children.iter().enumerate() {
+                    child.apply(values, t - idx as f32 * interval);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RunningTimeline {
+    timeline: Timeline,
+    elapsed: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct Timelines {
+    active: Vec<RunningTimeline>,
+}
+
+impl Timelines {
+    pub fn play(&mut self, timeline: Timeline) {
+        self.active.push(RunningTimeline { timeline, elapsed: 0. });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    // Moves every timeline on by `dt`, returning the values to apply and dropping timelines that have finished
+    pub fn advance(&mut self, dt: f32) -> Vec<(Property, f32)> {
+        let mut values = Vec::new();
+        for running in &mut self.active {
+            running.elapsed += dt;
+            running.timeline.apply(&mut values, running.elapsed);
+        }
+        self.active.retain(|e| e.elapsed < e.timeline.duration());
+        values
+    }
+}
+
+pub fn tick(app: &mut App, dt: f32) {
+    for (property, value) in app.timelines.advance(dt) {
+        set_property(app, property, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fade(c_idx: usize, duration: f32) -> Timeline {
+        Timeline::tween(Property::RowAlpha(c_idx), 0., 1., duration, Ease::Linear)
+    }
+
+    fn value_of(values: &[(Property, f32)], property: Property) -> Option<f32> {
+        values.iter().rev().find(|(p, _)| *p == property).map(|(_, value)| *value)
+    }
+
+    #[test]
+    fn duration_of_sequences_parallels_and_staggers() {
+        assert_eq!(
+            Timeline::Sequence(vec![fade(0, 0.5), Timeline::Delay(0.25), fade(1, 1.)]).duration(),
+            1.75
+        );
+        assert_eq!(Timeline::Parallel(vec![fade(0, 0.5), fade(1, 1.)]).duration(), 1.);
+        // The last child starting isn't necessarily the last to finish
+        let stagger = Timeline::Stagger {
+            interval: 0.25,
+            children: vec![fade(0, 1.), fade(1, 0.25), fade(2, 0.25)],
+        };
+        assert_eq!(stagger.duration(), 1.);
+    }
+
+    #[test]
+    fn stagger_starts_children_an_interval_apart() {
+        let mut timelines = Timelines::default();
+        timelines.play(Timeline::Stagger {
+            interval: 0.25,
+            children: vec![fade(0, 1.), fade(1, 1.), fade(2, 1.)],
+        });
+
+        let values = timelines.advance(0.375);
+        assert_eq!(value_of(&values, Property::RowAlpha(0)), Some(0.375));
+        assert_eq!(value_of(&values, Property::RowAlpha(1)), Some(0.125));
+        assert_eq!(value_of(&values, Property::RowAlpha(2)), None);
+    }
+
+    #[test]
+    fn sequence_runs_children_one_after_another() {
+        let mut timelines = Timelines::default();
+        timelines.play(Timeline::Sequence(vec![fade(0, 0.5), fade(1, 0.5)]));
+
+        let values = timelines.advance(0.25);
+        assert_eq!(value_of(&values, Property::RowAlpha(0)), Some(0.5));
+        assert_eq!(value_of(&values, Property::RowAlpha(1)), None);
+        let values = timelines.advance(0.5);
+        assert_eq!(value_of(&values, Property::RowAlpha(0)), Some(1.));
+        assert_eq!(value_of(&values, Property::RowAlpha(1)), Some(0.5));
+    }
+
+    #[test]
+    fn finished_timelines_land_on_their_targets_and_stop() {
+        let mut timelines = Timelines::default();
+        timelines.play(Timeline::Parallel(vec![fade(0, 0.5), fade(1, 1.)]));
+
+        timelines.advance(0.75);
+        assert!(!timelines.is_empty());
+        let values = timelines.advance(0.5);
+        assert_eq!(value_of(&values, Property::RowAlpha(0)), Some(1.));
+        assert_eq!(value_of(&values, Property::RowAlpha(1)), Some(1.));
+        assert!(timelines.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::util;
+use crate::App;
+
+// Rate at which a tween's position advances from 0 to 1 per second
+static DEFAULT_SPEED: f32 = 6.;
+
+// Explicit handle to an animatable value so a tween keeps driving the same value
+// even if the selection changes while it is in flight
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Property {
+    ViewportY,
+    RowScroll(usize),
+    RowAlpha(usize),
+    RowOffsetX(usize),
+    RowLines(usize),
+    TileScale(usize, usize),
+    TileAlpha(usize, usize),
+    TileOffsetY(usize, usize),
+    // Enter/exit transition of the screen with the given stack id
+    ScreenProgress(usize),
+}
+
+pub fn get_property(app: &App, property: Property) -> f32 {
+    match property {
+        Property::ViewportY => app.viewport.pos[1],
+        Property::RowScroll(c_idx) => app.containers.get(c_idx).map_or(0., |c| c.selected_tile_idx),
+        Property::RowAlpha(c_idx) => app.containers.get(c_idx).map_or(1., |c| c.alpha),
+        Property::RowOffsetX(c_idx) => app.containers.get(c_idx).map_or(0., |c| c.offset_x),
+        Property::RowLines(c_idx) => app.containers.get(c_idx).map_or(1., |c| c.lines),
+        Property::TileScale(c_idx, t_idx) => app.containers.get(c_idx).and_then(|c| c.images.get(t_idx)).map_or(1., |t| t.scale),
+        Property::TileAlpha(c_idx, t_idx) => app.containers.get(c_idx).and_then(|c| c.images.get(t_idx)).map_or(1., |t| t.alpha),
+        Property::TileOffsetY(c_idx, t_idx) => app
+            .containers
+            .get(c_idx)
+            .and_then(|c| c.images.get(t_idx))
+            .map_or(0., |t| t.offset_y),
+        Property::ScreenProgress(id) => app.screens.progress(id).unwrap_or(0.),
+    }
+}
+
+pub fn set_property(app: &mut App, property: Property, value: f32) {
+    match property {
+        Property::ViewportY => {
+            app.viewport.pos[1] = value;
+        }
+        Property::RowScroll(c_idx) => {
+            if let Some(container) = app.containers.get_mut(c_idx) {
+                container.selected_tile_idx = value;
+            }
+        }
+        Property::RowAlpha(c_idx) => {
+            if let Some(container) = app.containers.get_mut(c_idx) {
+                container.alpha = value;
+            }
+        }
+        Property::RowOffsetX(c_idx) => {
+            if let Some(container) = app.containers.get_mut(c_idx) {
+                container.offset_x = value;
+            }
+        }
+        Property::RowLines(c_idx) => {
+            if let Some(container) = app.containers.get_mut(c_idx) {
+                container.lines = value;
+            }
+        }
+        Property::TileScale(c_idx, t_idx) => {
+            if let Some(tile) = app.containers.get_mut(c_idx).and_then(|c| c.images.get_mut(t_idx)) {
+                tile.scale = value;
+            }
+        }
+        Property::TileAlpha(c_idx, t_idx) => {
+            if let Some(tile) = app.containers.get_mut(c_idx).and_then(|c| c.images.get_mut(t_idx)) {
+                tile.alpha = value;
+            }
+        }
+        Property::TileOffsetY(c_idx, t_idx) => {
+            if let Some(tile) = app.containers.get_mut(c_idx).and_then(|c| c.images.get_mut(t_idx)) {
+                tile.offset_y = value;
+            }
+        }
+        Property::ScreenProgress(id) => {
+            app.screens.set_progress(id, value);
+        }
+    }
+}
+
+// Called once a tween reaches its target
+pub type OnComplete = fn(&mut App);
+
+#[derive(Debug)]
+pub struct Tween {
+    pub property: Property,
+    pub from: f32,
+    pub to: f32,
+    pub position: f32,
+    pub speed: f32,
+    pub on_complete: Option<OnComplete>,
+    pub next: Option<Box<Tween>>,
+}
+
+impl Tween {
+    pub fn new(property: Property, from: f32, to: f32) -> Self {
+        Tween {
+            property,
+            from,
+            to,
+            position: 0.,
+            speed: DEFAULT_SPEED,
+            on_complete: None,
+            next: None,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn on_complete(mut self, callback: OnComplete) -> Self {
+        self.on_complete = Some(callback);
+        self
+    }
+
+    // Appends a tween to run once this one (and anything already chained) completes
+    pub fn then(mut self, next: Tween) -> Self {
+        match self.next {
+            Some(chained) => {
+                self.next = Some(Box::new(chained.then(next)));
+            }
+            None => {
+                self.next = Some(Box::new(next));
+            }
+        }
+        self
+    }
+
+    fn value(&self) -> f32 {
+        (1. - self.position) * self.from + self.position * self.to
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Tweens {
+    active: Vec<Tween>,
+}
+
+impl Tweens {
+    // Starts a tween, replacing any in-flight tween on the same property
+    pub fn start(&mut self, tween: Tween) {
+        self.active.retain(|e| e.property != tween.property);
+        self.active.push(tween);
+    }
+
+    pub fn cancel(&mut self, property: Property) {
+        self.active.retain(|e| e.property != property);
+    }
+
+    pub fn target_of(&self, property: Property) -> Option<f32> {
+        self.active.iter().find(|e| e.property == property).map(|e| e.to)
+    }
+
+    pub fn is_animating(&self, property: Property) -> bool {
+        self.active.iter().any(|e| e.property == property)
+    }
+
+    // Heads for `to` from the property's current value, leaving a tween already heading there alone
+    pub fn retarget(&mut self, property: Property, current: f32, to: f32) {
+        if self.target_of(property) == Some(to) {
+            return;
+        }
+        self.start(Tween::new(property, current, to));
+    }
+
+    // Moves every tween on by `dt`, returning the values to apply and the callbacks of tweens that finished.
+    // Finished tweens hand over to whatever was chained after them.
+    pub fn advance(&mut self, dt: f32) -> (Vec<(Property, f32)>, Vec<OnComplete>) {
+        let mut values = Vec::new();
+        for tween in &mut self.active {
+            tween.position = util::clamp(tween.position + dt * tween.speed, 0., 1.);
+            values.push((tween.property, tween.value()));
+        }
+
+        let (finished, running): (Vec<Tween>, Vec<Tween>) = std::mem::take(&mut self.active).into_iter().partition(|e| e.position == 1.);
+        self.active = running;
+
+        let mut callbacks = Vec::new();
+        for tween in finished {
+            callbacks.extend(tween.on_complete);
+            if let Some(next) = tween.next {
+                self.start(*next);
+            }
+        }
+        (values, callbacks)
+    }
+}
+
+// Retargets the property towards `to`, starting from wherever it currently is
+pub fn animate_to(app: &mut App, property: Property, to: f32) {
+    let current = get_property(app, property);
+    app.tweens.retarget(property, current, to);
+}
+
+pub fn tick(app: &mut App, dt: f32) {
+    let (values, callbacks) = app.tweens.advance(dt);
+    for (property, value) in values {
+        set_property(app, property, value);
+    }
+    for callback in callbacks {
+        callback(app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DT: f32 = 1. / 60.;
+
+    // Value the tweens last set for the property
+    fn value_of(values: &[(Property, f32)], property: Property) -> Option<f32> {
+        values.iter().rev().find(|(p, _)| *p == property).map(|(_, value)| *value)
+    }
+
+    #[test]
+    fn replaced_tween_starts_from_the_current_value() {
+        let mut tweens = Tweens::default();
+        tweens.start(Tween::new(Property::ViewportY, 0., 100.));
+        let (values, _) = tweens.advance(DT * 5.);
+        let current = value_of(&values, Property::ViewportY).unwrap();
+        assert!(current > 0. && current < 100.);
+
+        tweens.retarget(Property::ViewportY, current, -100.);
+        let (values, _) = tweens.advance(0.);
+        assert_eq!(value_of(&values, Property::ViewportY), Some(current));
+    }
+
+    #[test]
+    fn retargeting_keeps_going_the_same_way() {
+        let mut tweens = Tweens::default();
+        let mut current = 0.;
+        tweens.retarget(Property::ViewportY, current, 100.);
+        for _ in 0..5 {
+            let (values, _) = tweens.advance(DT);
+            current = value_of(&values, Property::ViewportY).unwrap();
+        }
+
+        // Pushing further along the same way never turns the value back
+        tweens.retarget(Property::ViewportY, current, 200.);
+        // Retargeting to where it's already heading keeps the tween in flight
+        tweens.retarget(Property::ViewportY, current, 200.);
+        for _ in 0..5 {
+            let (values, _) = tweens.advance(DT);
+            let next = value_of(&values, Property::ViewportY).unwrap();
+            assert!(next > current);
+            current = next;
+        }
+    }
+
+    #[test]
+    fn chained_tweens_run_in_order() {
+        let mut tweens = Tweens::default();
+        tweens.start(Tween::new(Property::RowAlpha(0), 0., 1.).then(Tween::new(Property::RowOffsetX(0), -80., 0.)));
+
+        let mut first_done = None;
+        let mut second_started = None;
+        for frame in 0..120 {
+            let (values, _) = tweens.advance(DT);
+            if first_done.is_none() && value_of(&values, Property::RowAlpha(0)) == Some(1.) {
+                first_done = Some(frame);
+            }
+            if second_started.is_none() && value_of(&values, Property::RowOffsetX(0)).is_some() {
+                second_started = Some(frame);
+            }
+        }
+        assert!(first_done.unwrap() < second_started.unwrap());
+        assert!(!tweens.is_animating(Property::RowAlpha(0)));
+        assert!(!tweens.is_animating(Property::RowOffsetX(0)));
+    }
+}
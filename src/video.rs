@@ -0,0 +1,438 @@
+use sfml::graphics::Image;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+
+use crate::focus::FocusId;
+use crate::screen::ScreenKind;
+use crate::{app_gl, App};
+
+// How long a tile must keep focus before its preview starts
+static PREVIEW_DWELL: f32 = 1.5;
+static CROSSFADE_DURATION: f32 = 0.5;
+static DEFAULT_FPS: f32 = 24.;
+// Frames decoded ahead of playback; the decoder waits once this many are queued
+static DECODE_AHEAD: usize = 4;
+
+// Local file a preview is decoded from
+#[derive(Debug, Clone)]
+enum VideoSource {
+    // Directory of still images, played in file name order
+    Frames { dir: String, fps: f32 },
+    // Motion JPEG: JPEG images stored back to back in one file
+    Mjpeg { path: String, fps: f32 },
+}
+
+impl VideoSource {
+    fn fps(&self) -> f32 {
+        match self {
+            VideoSource::Frames { fps, .. } | VideoSource::Mjpeg { fps, .. } => *fps,
+        }
+    }
+}
+
+// Previews available for items, by item id
+#[derive(Debug, Default)]
+pub struct VideoPreviews {
+    sources: HashMap<String, VideoSource>,
+}
+
+impl VideoPreviews {
+    // Loads a manifest of the form { "<content id>": { "frames": "res/previews/x", "fps": 24 }, "<content id>": { "mjpeg": "res/previews/y.mjpeg" }, ... }
+    pub fn load_from_disk(path: &str) -> Result<VideoPreviews, String> {
+        let mut f = File::open(path).map_err(|err| format!("Failed to open video preview manifest {}: {:?}", path, err))?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)
+            .map_err(|err| format!("Failed to read video preview manifest {}: {:?}", path, err))?;
+        let data: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Malformed video preview manifest {}: {:?}", path, err))?;
+        let entries = data
+            .as_object()
+            .ok_or(format!("Video preview manifest {} should be an object", path))?;
+
+        let mut sources = HashMap::new();
+        for (id, entry) in entries {
+            let fps = entry["fps"].as_f64().map(|fps| fps as f32).unwrap_or(DEFAULT_FPS);
+            if fps <= 0. {
+                return Err(format!("Video preview for {} should have a positive fps", id));
+            }
+            let source = match (entry["frames"].as_str(), entry["mjpeg"].as_str()) {
+                (Some(dir), None) => VideoSource::Frames { dir: dir.to_string(), fps },
+                (None, Some(path)) => VideoSource::Mjpeg {
+                    path: path.to_string(),
+                    fps,
+                },
+                _ => return Err(format!("Video preview for {} needs one of \"frames\" or \"mjpeg\"", id)),
+            };
+            sources.insert(id.to_string(), source);
+        }
+
+        Ok(VideoPreviews { sources })
+    }
+}
+
+#[derive(Debug)]
+struct DecodedFrame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+// Turns one encoded frame into pixels, swappable so playback can run without a window
+type DecodeFrame = fn(&[u8]) -> Option<DecodedFrame>;
+
+fn decode_image(bytes: &[u8]) -> Option<DecodedFrame> {
+    let image = Image::from_memory(bytes)?;
+    let size = image.size();
+    Some(DecodedFrame {
+        width: size.x,
+        height: size.y,
+        pixels: image.pixel_data().to_vec(),
+    })
+}
+
+// Splits a Motion JPEG file at each image's start and end markers
+fn split_mjpeg(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut images = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = bytes[offset..].windows(2).position(|w| w == [0xFF, 0xD8]) {
+        let start = offset + start;
+        match bytes[start..].windows(2).position(|w| w == [0xFF, 0xD9]) {
+            Some(end) => {
+                images.push(&bytes[start..start + end + 2]);
+                offset = start + end + 2;
+            }
+            None => break,
+        }
+    }
+    images
+}
+
+fn read_frame_files(dir: &str) -> Result<Vec<Vec<u8>>, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map_err(|err| format!("Failed to read video preview frames {}: {:?}", dir, err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| fs::read(path).map_err(|err| format!("Failed to read video preview frame {:?}: {:?}", path, err)))
+        .collect()
+}
+
+// Decodes the preview on its own thread, looping until playback drops the receiver
+fn spawn_decoder(source: VideoSource, decode: DecodeFrame) -> Receiver<DecodedFrame> {
+    let (tx, rx): (SyncSender<DecodedFrame>, Receiver<DecodedFrame>) = mpsc::sync_channel(DECODE_AHEAD);
+    thread::spawn(move || {
+        let encoded = match &source {
+            VideoSource::Frames { dir, .. } => read_frame_files(dir),
+            VideoSource::Mjpeg { path, .. } => fs::read(path)
+                .map(|bytes| split_mjpeg(&bytes).iter().map(|image| image.to_vec()).collect())
+                .map_err(|err| format!("Failed to read video preview {}: {:?}", path, err)),
+        };
+        let encoded = match encoded {
+            Ok(encoded) => encoded,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+
+        // Frames are decoded as they are needed so only a few are ever held decoded
+        loop {
+            let mut decoded_any = false;
+            for frame in encoded.iter().filter_map(|bytes| decode(bytes)) {
+                decoded_any = true;
+                if tx.send(frame).is_err() {
+                    return;
+                }
+            }
+            if !decoded_any {
+                println!("Video preview has no decodable frames: {:?}", source);
+                return;
+            }
+        }
+    });
+    rx
+}
+
+#[derive(Debug)]
+struct Playing {
+    frames: Receiver<DecodedFrame>,
+    frame_duration: f32,
+    frame_time: f32,
+    frames_shown: usize,
+    // Texture rewritten with each new frame, created with the first one
+    texture: Option<(u32, u32, u32)>,
+    fade: f32,
+}
+
+impl Drop for Playing {
+    fn drop(&mut self) {
+        if let Some((texture_id, _, _)) = self.texture {
+            app_gl::release_texture(texture_id);
+        }
+    }
+}
+
+// Plays a preview on the focused tile once focus has rested on it for a while
+#[derive(Debug, Default)]
+pub struct VideoPlayer {
+    tile: Option<(usize, usize)>,
+    dwell: f32,
+    playing: Option<Playing>,
+}
+
+impl VideoPlayer {
+    pub fn is_playing(&self) -> bool {
+        matches!(&self.playing, Some(playing) if playing.texture.is_some())
+    }
+
+    // Current frame for the tile and how far it has faded in over the artwork
+    pub fn frame_for(&self, c_idx: usize, t_idx: usize) -> Option<(u32, f32)> {
+        if self.tile != Some((c_idx, t_idx)) {
+            return None;
+        }
+        let playing = self.playing.as_ref()?;
+        playing.texture.map(|(texture_id, _, _)| (texture_id, playing.fade))
+    }
+}
+
+fn show_frame(playing: &mut Playing, frame: DecodedFrame) {
+    match playing.texture {
+        Some((texture_id, width, height)) if width == frame.width && height == frame.height => {
+            app_gl::update_rgba(texture_id, width, height, &frame.pixels);
+        }
+        _ => {
+            if let Some((texture_id, _, _)) = playing.texture {
+                app_gl::release_texture(texture_id);
+            }
+            let texture_id = app_gl::upload_rgba(frame.width, frame.height, &frame.pixels);
+            playing.texture = Some((texture_id, frame.width, frame.height));
+        }
+    }
+}
+
+impl VideoPlayer {
+    // Moves playback on for the focused tile, returning a frame that is now due on screen. `ready` is
+    // whether the tile's artwork is in, and `source` finds the tile's preview once focus has dwelt on it.
+    fn advance(
+        &mut self,
+        tile: Option<(usize, usize)>,
+        ready: bool,
+        source: impl FnOnce() -> Option<VideoSource>,
+        decode: DecodeFrame,
+        dt: f32,
+    ) -> Option<DecodedFrame> {
+        if self.tile != tile {
+            self.tile = tile;
+            self.dwell = 0.;
+            self.playing = None;
+        }
+        tile?;
+
+        if self.playing.is_none() {
+            self.dwell += dt;
+            // Only start once the artwork is in, so the preview has something to crossfade from
+            if self.dwell < PREVIEW_DWELL || !ready {
+                return None;
+            }
+            let source = source()?;
+            self.playing = Some(Playing {
+                frame_duration: 1. / source.fps(),
+                frames: spawn_decoder(source, decode),
+                frame_time: 0.,
+                frames_shown: 0,
+                texture: None,
+                fade: 0.,
+            });
+        }
+
+        let playing = self.playing.as_mut()?;
+        playing.frame_time += dt;
+        let mut due = None;
+        if playing.frames_shown == 0 || playing.frame_time >= playing.frame_duration {
+            // Nothing new while the decoder is behind, or after it gave up, keeps the last frame up
+            if let Ok(frame) = playing.frames.try_recv() {
+                // The first frame gets a whole frame however long the decoder took to start. After that, time the
+                // decoder fell behind by is dropped rather than rushing to catch up.
+                playing.frame_time = if playing.frames_shown == 0 {
+                    0.
+                } else {
+                    (playing.frame_time - playing.frame_duration).clamp(0., playing.frame_duration)
+                };
+                playing.frames_shown += 1;
+                due = Some(frame);
+            }
+        }
+        if playing.frames_shown > 0 {
+            playing.fade = (playing.fade + dt / CROSSFADE_DURATION).min(1.);
+        }
+        due
+    }
+}
+
+pub fn tick(app: &mut App, dt: f32) {
+    let tile = match app.focus.current() {
+        Some(FocusId::Tile(c_idx, t_idx)) if app.screens.active_kind() == Some(ScreenKind::Home) => Some((c_idx, t_idx)),
+        _ => None,
+    };
+    let containers = &app.containers;
+    let image = tile.and_then(|(c_idx, t_idx)| containers.get(c_idx)?.images.get(t_idx));
+    let ready = image.is_some_and(|image| image.texture().is_some());
+    let id = image.and_then(|image| image.item.id.as_ref());
+    let previews = &app.video_previews;
+    let source = || previews.sources.get(id?).cloned();
+
+    if let Some(frame) = app.video.advance(tile, ready, source, decode_image, dt) {
+        if let Some(playing) = app.video.playing.as_mut() {
+            show_frame(playing, frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    static FIXTURES: &str = "tests/fixtures/video";
+
+    // Stands in for image decoding, which needs SFML, by reporting each frame's size in bytes
+    fn byte_count(bytes: &[u8]) -> Option<DecodedFrame> {
+        Some(DecodedFrame {
+            width: bytes.len() as u32,
+            height: 1,
+            pixels: Vec::new(),
+        })
+    }
+
+    fn fixture_previews() -> VideoPreviews {
+        VideoPreviews::load_from_disk(&format!("{}/previews.json", FIXTURES)).unwrap()
+    }
+
+    fn load_manifest(name: &str, contents: &str) -> Result<VideoPreviews, String> {
+        let path = std::env::temp_dir().join(format!("sfml_example_{}_{}.json", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let result = VideoPreviews::load_from_disk(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    // Steps the player by `dt` until the decoder has delivered a frame
+    fn advance_until_frame(player: &mut VideoPlayer, source: &VideoSource, dt: f32) -> DecodedFrame {
+        let started = Instant::now();
+        loop {
+            if let Some(frame) = player.advance(Some((0, 0)), true, || Some(source.clone()), byte_count, dt) {
+                return frame;
+            }
+            assert!(started.elapsed() < Duration::from_secs(5), "decoder produced no frames");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn splits_mjpeg_into_images() {
+        let bytes = fs::read(format!("{}/preview.mjpeg", FIXTURES)).unwrap();
+        let images = split_mjpeg(&bytes);
+        assert_eq!(images.len(), 3);
+        assert_eq!(images.iter().map(|image| image.len()).sum::<usize>(), bytes.len());
+        assert!(images
+            .iter()
+            .all(|image| image.starts_with(&[0xFF, 0xD8]) && image.ends_with(&[0xFF, 0xD9])));
+
+        // Bytes around the images and an unfinished image are ignored
+        let mut noisy = vec![0, 1, 2];
+        noisy.extend_from_slice(images[0]);
+        noisy.extend_from_slice(&[7, 0xFF, 0xD8, 9]);
+        assert_eq!(split_mjpeg(&noisy), vec![images[0]]);
+    }
+
+    #[test]
+    fn loads_manifests() {
+        let previews = fixture_previews();
+        match &previews.sources["frames-item"] {
+            VideoSource::Frames { dir, fps } => assert_eq!((dir.as_str(), *fps), ("tests/fixtures/video/frames", 12.)),
+            other => panic!("unexpected source {:?}", other),
+        }
+        assert_eq!(previews.sources["mjpeg-item"].fps(), DEFAULT_FPS);
+    }
+
+    #[test]
+    fn rejects_bad_manifests() {
+        assert!(load_manifest("list", "[]").is_err());
+        assert!(load_manifest("neither", r#"{ "a": { "fps": 24 } }"#).is_err());
+        assert!(load_manifest("both", r#"{ "a": { "frames": "x", "mjpeg": "y" } }"#).is_err());
+        assert!(load_manifest("fps", r#"{ "a": { "frames": "x", "fps": 0 } }"#).is_err());
+        assert!(VideoPreviews::load_from_disk("tests/fixtures/video/missing.json").is_err());
+    }
+
+    #[test]
+    fn reads_frame_files_in_name_order() {
+        let frames = read_frame_files(&format!("{}/frames", FIXTURES)).unwrap();
+        let expected: Vec<Vec<u8>> = ["000.png", "001.png", "002.png"]
+            .iter()
+            .map(|name| fs::read(format!("{}/frames/{}", FIXTURES, name)).unwrap())
+            .collect();
+        assert_eq!(frames, expected);
+    }
+
+    #[test]
+    fn decoder_loops_over_the_preview() {
+        let bytes = fs::read(format!("{}/preview.mjpeg", FIXTURES)).unwrap();
+        let sizes: Vec<u32> = split_mjpeg(&bytes).iter().map(|image| image.len() as u32).collect();
+        let source = fixture_previews().sources["mjpeg-item"].clone();
+        let decoded: Vec<u32> = spawn_decoder(source, byte_count).iter().take(7).map(|frame| frame.width).collect();
+        let expected: Vec<u32> = sizes.iter().cycle().take(7).copied().collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn preview_waits_for_dwell_and_artwork() {
+        let source = fixture_previews().sources["frames-item"].clone();
+        let mut player = VideoPlayer::default();
+        let tile = Some((0, 0));
+        assert!(player.advance(tile, true, || Some(source.clone()), byte_count, 1.).is_none());
+        assert!(player.playing.is_none());
+        // Artwork still loading holds the preview back even after the dwell
+        assert!(player.advance(tile, false, || Some(source.clone()), byte_count, 1.).is_none());
+        assert!(player.playing.is_none());
+        // Tiles without a preview never start one
+        player.advance(tile, true, || None, byte_count, 0.);
+        assert!(player.playing.is_none());
+
+        // Moving focus starts the dwell over
+        player.advance(Some((0, 1)), true, || Some(source.clone()), byte_count, 1.);
+        assert!(player.playing.is_none());
+        advance_until_frame(&mut player, &source, 0.5);
+        assert!(player.playing.is_some());
+        assert!(player.dwell >= PREVIEW_DWELL);
+    }
+
+    #[test]
+    fn preview_crossfades_in_and_keeps_frame_rate() {
+        let source = fixture_previews().sources["frames-item"].clone();
+        let mut player = VideoPlayer::default();
+        player.advance(Some((0, 0)), true, || Some(source.clone()), byte_count, PREVIEW_DWELL);
+        advance_until_frame(&mut player, &source, 0.);
+        assert_eq!(player.playing.as_ref().unwrap().fade, 0.);
+
+        // 12 fps: the next frame is due after 1/12 s, with the fade running meanwhile
+        thread::sleep(Duration::from_millis(50));
+        assert!(player.advance(Some((0, 0)), true, || None, byte_count, 0.05).is_none());
+        advance_until_frame(&mut player, &source, 0.05);
+        let playing = player.playing.as_ref().unwrap();
+        assert_eq!(playing.frames_shown, 2);
+        assert!((playing.fade - 0.2).abs() < 1e-5);
+
+        player.advance(Some((0, 0)), true, || None, byte_count, CROSSFADE_DURATION);
+        assert_eq!(player.playing.as_ref().unwrap().fade, 1.);
+
+        // Losing focus stops the preview at once
+        player.advance(None, true, || None, byte_count, 0.);
+        assert!(player.playing.is_none());
+    }
+}